@@ -0,0 +1,43 @@
+//! Print the position of a potentiometer between VDD and GND, with its wiper on PD4 (A7)
+#![no_std]
+#![no_main]
+
+use core::fmt::Write as _;
+use embedded_hal_1::delay::DelayNs;
+use panic_halt as _;
+
+use ch32v00x_hal as hal;
+
+use hal::adc::{self, Adc};
+use hal::delay::CycleDelay;
+use hal::prelude::*;
+use hal::serial::Config;
+
+#[qingke_rt::entry]
+fn main() -> ! {
+    let p = ch32v0::ch32v003::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
+    let clocks = rcc.config.freeze();
+
+    let gpiod = p.GPIOD.split(&mut rcc);
+
+    let tx = gpiod.pd5.into_alternate();
+    let rx = gpiod.pd6.into_floating_input();
+    let mut usart = p
+        .USART1
+        .usart(tx, rx, Config::default(), &mut afio.pcfr, &mut rcc, &clocks);
+
+    let mut wiper = gpiod.pd4.into_analog();
+    let mut adc = Adc::new(p.ADC1, &mut rcc, &clocks);
+    let mut delay = CycleDelay::new(&clocks);
+
+    loop {
+        let raw = adc.read(&mut wiper);
+        let percent = raw as u32 * 100 / adc::MAX_VALUE as u32;
+        writeln!(usart, "{raw} ({percent}%)\r").ok();
+
+        delay.delay_ms(200);
+    }
+}
@@ -1,8 +1,10 @@
 #![no_std]
 #![no_main]
 
+use embedded_hal_1::delay::DelayNs;
 use panic_halt as _;
 
+use ch32v00x_hal::delay::SysDelay;
 use ch32v00x_hal::prelude::*;
 
 #[qingke_rt::entry]
@@ -12,17 +14,16 @@ fn main() -> ! {
     let p = ch32v0::ch32v003::Peripherals::take().unwrap();
 
     let mut rcc = p.RCC.constrain();
-    let _clocks = rcc.config.freeze();
+    let clocks = rcc.config.freeze();
 
     let gpiod = p.GPIOD.split(&mut rcc);
 
     let mut led = gpiod.pd6.into_push_pull_output();
+    let mut delay = SysDelay::new(p.SYSTICK, &clocks);
 
     loop {
         led.toggle();
 
-        unsafe {
-            qingke::riscv::asm::delay(10000000);
-        }
+        delay.delay_ms(500);
     }
 }
@@ -1,12 +1,13 @@
 #![no_std]
 #![no_main]
 
+use embedded_hal_1::delay::DelayNs;
 use hal::println;
 use panic_halt as _;
 
 use ch32v00x_hal as hal;
 use ch32v00x_hal::prelude::*;
-use qingke::riscv;
+use hal::delay::SysDelay;
 
 #[qingke_rt::entry]
 fn main() -> ! {
@@ -18,18 +19,17 @@ fn main() -> ! {
     let p = ch32v0::ch32v003::Peripherals::take().unwrap();
 
     let mut rcc = p.RCC.constrain();
-    let _clocks = rcc.config.freeze();
+    let clocks = rcc.config.freeze();
 
     let gpiod = p.GPIOD.split(&mut rcc);
 
     let mut led = gpiod.pd6.into_push_pull_output();
+    let mut delay = SysDelay::new(p.SYSTICK, &clocks);
 
     loop {
         led.toggle();
         println!("led toggle");
 
-        unsafe {
-            riscv::asm::delay(10000000);
-        }
+        delay.delay_ms(500);
     }
 }
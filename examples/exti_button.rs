@@ -0,0 +1,60 @@
+//! Toggle an LED on PD6 from the interrupt of a button on PD2, connected to ground.
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use panic_halt as _;
+
+use ch32v0::ch32v003 as pac;
+use ch32v00x_hal as hal;
+
+use hal::gpio::{gpiod::*, Edge, ExtiPin, Input, Output, PullUp, PushPull};
+use hal::prelude::*;
+
+static BUTTON: Mutex<RefCell<Option<PD2<Input<PullUp>>>>> = Mutex::new(RefCell::new(None));
+static LED: Mutex<RefCell<Option<PD6<Output<PushPull>>>>> = Mutex::new(RefCell::new(None));
+
+#[qingke_rt::interrupt]
+fn EXTI7_0() {
+    critical_section::with(|cs| {
+        let mut button = BUTTON.borrow_ref_mut(cs);
+        let button = button.as_mut().unwrap();
+
+        if button.check_interrupt() {
+            button.clear_interrupt_pending_bit();
+
+            if let Some(led) = LED.borrow_ref_mut(cs).as_mut() {
+                led.toggle();
+            }
+        }
+    });
+}
+
+#[qingke_rt::entry]
+fn main() -> ! {
+    let mut p = pac::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
+    let _clocks = rcc.config.freeze();
+
+    let d = p.GPIOD.split(&mut rcc);
+    let led = d.pd6.into_push_pull_output();
+    let mut button = d.pd2.into_pull_up_input();
+
+    button.make_interrupt_source(&mut afio.exticr);
+    button.trigger_on_edge(&mut p.EXTI, Edge::Falling);
+    button.enable_interrupt(&mut p.EXTI);
+
+    critical_section::with(|cs| {
+        BUTTON.borrow_ref_mut(cs).replace(button);
+        LED.borrow_ref_mut(cs).replace(led);
+    });
+
+    unsafe { qingke::pfic::enable_interrupt(pac::Interrupt::EXTI7_0 as u8) };
+
+    loop {
+        qingke::riscv::asm::wfi();
+    }
+}
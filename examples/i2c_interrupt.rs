@@ -0,0 +1,89 @@
+//! Interrupt driven I2C write, turning on an SSD1306 display connected to PC1/PC2.
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use panic_halt as _;
+
+use ch32v0::ch32v003 as pac;
+use ch32v00x_hal as hal;
+
+use hal::gpio::{gpioc::*, Alternate, OpenDrain};
+use hal::{i2c::*, prelude::*};
+
+type Bus = I2cInterrupt<PC2<Alternate<OpenDrain>>, PC1<Alternate<OpenDrain>>>;
+
+static I2C: Mutex<RefCell<Option<Bus>>> = Mutex::new(RefCell::new(None));
+
+/// SSD1306 command stream: display on
+static DISPLAY_ON: [u8; 2] = [0x00, 0xAF];
+
+#[qingke_rt::interrupt]
+fn I2C1_EV() {
+    critical_section::with(|cs| {
+        if let Some(i2c) = I2C.borrow_ref_mut(cs).as_mut() {
+            i2c.on_interrupt();
+        }
+    });
+}
+
+#[qingke_rt::interrupt]
+fn I2C1_ER() {
+    critical_section::with(|cs| {
+        if let Some(i2c) = I2C.borrow_ref_mut(cs).as_mut() {
+            i2c.on_interrupt();
+        }
+    });
+}
+
+#[qingke_rt::entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
+    let clocks = rcc.config.freeze();
+
+    let c = p.GPIOC.split(&mut rcc);
+    let sda = c.pc1.into_alternate_open_drain();
+    let scl = c.pc2.into_alternate_open_drain();
+
+    let i2c = I2c::i2c1(
+        p.I2C1,
+        scl,
+        sda,
+        I2cConfig::fast_mode(),
+        &mut afio.pcfr,
+        &mut rcc,
+        &clocks,
+    );
+    critical_section::with(|cs| I2C.borrow_ref_mut(cs).replace(I2cInterrupt::new(i2c)));
+
+    unsafe {
+        qingke::pfic::enable_interrupt(pac::Interrupt::I2C1_EV as u8);
+        qingke::pfic::enable_interrupt(pac::Interrupt::I2C1_ER as u8);
+    }
+
+    critical_section::with(|cs| {
+        let mut i2c = I2C.borrow_ref_mut(cs);
+        i2c.as_mut().unwrap().start_write(0x3C, &DISPLAY_ON).ok();
+    });
+
+    // The transfer runs in the background, the CPU is free to do something else
+    let result = loop {
+        let result = critical_section::with(|cs| I2C.borrow_ref_mut(cs).as_mut().unwrap().poll());
+        match result {
+            Err(nb::Error::WouldBlock) => continue,
+            Err(nb::Error::Other(e)) => break Err(e),
+            Ok(()) => break Ok(()),
+        }
+    };
+
+    result.unwrap();
+
+    loop {
+        // Nothing left to do
+        qingke::riscv::asm::wfi();
+    }
+}
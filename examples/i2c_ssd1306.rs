@@ -17,6 +17,7 @@ fn main() -> ! {
 
     // Configure clocks
     let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
     let clocks = rcc.config.freeze();
 
     // enable GPIO power domains
@@ -27,7 +28,15 @@ fn main() -> ! {
     let scl = c.pc2.into_alternate_open_drain();
 
     // Initialize i2c peripheral
-    let i2c = I2c::i2c1(p.I2C1, scl, sda, I2cConfig::fast_mode(), &mut rcc, &clocks);
+    let i2c = I2c::i2c1(
+        p.I2C1,
+        scl,
+        sda,
+        I2cConfig::fast_mode(),
+        &mut afio.pcfr,
+        &mut rcc,
+        &clocks,
+    );
 
     // Initialize display
     let i2c = I2CDisplayInterface::new(i2c);
@@ -0,0 +1,38 @@
+//! Fade an LED on PD6 in and out, with TIM2 channel 3 (full remap) at 1 kHz.
+#![no_std]
+#![no_main]
+
+use embedded_hal_1::{delay::DelayNs, pwm::SetDutyCycle};
+use panic_halt as _;
+
+use ch32v00x_hal as hal;
+
+use hal::delay::CycleDelay;
+use hal::gpio::NoPin;
+use hal::prelude::*;
+use hal::timer::Timer;
+
+#[qingke_rt::entry]
+fn main() -> ! {
+    let p = ch32v0::ch32v003::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
+    let clocks = rcc.config.freeze();
+
+    let gpiod = p.GPIOD.split(&mut rcc);
+    let led = gpiod.pd6.into_alternate();
+
+    let mut timer = Timer::new(p.TIM2, &mut rcc, &clocks);
+    let (_, _, mut pwm, _) = timer.pwm((NoPin, NoPin, led, NoPin), 1.kHz(), &mut afio.pcfr);
+    pwm.enable();
+
+    let mut delay = CycleDelay::new(&clocks);
+
+    loop {
+        for percent in (0..=100).chain((0..100).rev()) {
+            pwm.set_duty_cycle_percent(percent).ok();
+            delay.delay_ms(10);
+        }
+    }
+}
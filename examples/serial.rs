@@ -17,6 +17,7 @@ fn main() -> ! {
     let p = ch32v0::ch32v003::Peripherals::take().unwrap();
 
     let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
     let clocks = rcc.config.freeze();
 
     let gpiod = p.GPIOD.split(&mut rcc);
@@ -26,7 +27,9 @@ fn main() -> ! {
 
     let usart_config = Config::default();
 
-    let mut usart = p.USART1.usart(tx, rx, usart_config, &mut rcc, &clocks);
+    let mut usart = p
+        .USART1
+        .usart(tx, rx, usart_config, &mut afio.pcfr, &mut rcc, &clocks);
 
     let flash_size = hal::signature::flash_size_kb();
     let uid = hal::signature::unique_id();
@@ -0,0 +1,56 @@
+//! Receive on PD6 from the USART1 interrupt into a ring buffer, and echo from the main loop.
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use panic_halt as _;
+
+use ch32v0::ch32v003 as pac;
+use ch32v00x_hal as hal;
+
+use hal::prelude::*;
+use hal::serial::{Config, RingBuffer, UsartRx};
+
+static RX: Mutex<RefCell<Option<UsartRx>>> = Mutex::new(RefCell::new(None));
+static BUFFER: Mutex<RefCell<RingBuffer<64>>> = Mutex::new(RefCell::new(RingBuffer::new()));
+
+#[qingke_rt::interrupt]
+fn USART1() {
+    critical_section::with(|cs| {
+        if let Some(rx) = RX.borrow_ref_mut(cs).as_mut() {
+            // Errors only discard the broken bytes
+            let _ = rx.on_interrupt(&mut BUFFER.borrow_ref_mut(cs));
+        }
+    });
+}
+
+#[qingke_rt::entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
+    let clocks = rcc.config.freeze();
+
+    let gpiod = p.GPIOD.split(&mut rcc);
+    let tx = gpiod.pd5.into_alternate();
+    let rx = gpiod.pd6.into_floating_input();
+
+    let usart = p
+        .USART1
+        .usart(tx, rx, Config::default(), &mut afio.pcfr, &mut rcc, &clocks);
+    let (mut tx, mut rx, _pins) = usart.split();
+
+    rx.listen();
+    critical_section::with(|cs| RX.borrow_ref_mut(cs).replace(rx));
+
+    unsafe { qingke::pfic::enable_interrupt(pac::Interrupt::USART1 as u8) };
+
+    loop {
+        while let Some(byte) = critical_section::with(|cs| BUFFER.borrow_ref_mut(cs).pop()) {
+            tx.write_byte(byte);
+        }
+        qingke::riscv::asm::wfi();
+    }
+}
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+use core::fmt::Write as _;
+use panic_halt as _;
+
+use ch32v00x_hal as hal;
+
+use hal::prelude::*;
+use hal::serial::Config;
+use hal::spi::{Spi, SpiConfig, MODE_0};
+
+// Connect MOSI (PC6) to MISO (PC7), every byte sent is received back
+#[qingke_rt::entry]
+fn main() -> ! {
+    let p = ch32v0::ch32v003::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let mut afio = p.AFIO.constrain(&mut rcc);
+    let clocks = rcc.config.freeze();
+
+    let gpioc = p.GPIOC.split(&mut rcc);
+    let gpiod = p.GPIOD.split(&mut rcc);
+
+    let tx = gpiod.pd5.into_alternate();
+    let rx = gpiod.pd6.into_floating_input();
+    let mut usart = p
+        .USART1
+        .usart(tx, rx, Config::default(), &mut afio.pcfr, &mut rcc, &clocks);
+
+    let sck = gpioc.pc5.into_alternate();
+    let miso = gpioc.pc7.into_floating_input();
+    let mosi = gpioc.pc6.into_alternate();
+
+    let mut spi = Spi::spi1(
+        p.SPI1,
+        (sck, miso, mosi),
+        SpiConfig::new(MODE_0, 1.MHz()),
+        &mut afio.pcfr,
+        &mut rcc,
+        &clocks,
+    );
+
+    let sent = *b"loopback";
+    let mut buffer = sent;
+    match spi.transfer(&mut buffer) {
+        Ok(()) if buffer == sent => writeln!(usart, "loopback ok\r").ok(),
+        Ok(()) => writeln!(usart, "loopback mismatch: {:02x?}\r", buffer).ok(),
+        Err(e) => writeln!(usart, "loopback error: {:?}\r", e).ok(),
+    };
+
+    loop {
+        qingke::riscv::asm::wfi();
+    }
+}
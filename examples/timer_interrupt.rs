@@ -0,0 +1,57 @@
+//! Blink an LED on PD6 from the TIM2 update interrupt, at 1 Hz.
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use panic_halt as _;
+
+use ch32v0::ch32v003 as pac;
+use ch32v00x_hal as hal;
+
+use hal::gpio::{gpiod::PD6, Output, PushPull};
+use hal::pac::TIM2;
+use hal::prelude::*;
+use hal::timer::{Event, Timer};
+
+static TIMER: Mutex<RefCell<Option<Timer<TIM2>>>> = Mutex::new(RefCell::new(None));
+static LED: Mutex<RefCell<Option<PD6<Output<PushPull>>>>> = Mutex::new(RefCell::new(None));
+
+#[qingke_rt::interrupt]
+fn TIM2() {
+    critical_section::with(|cs| {
+        if let Some(timer) = TIMER.borrow_ref_mut(cs).as_mut() {
+            timer.clear_interrupt(Event::Update);
+        }
+        if let Some(led) = LED.borrow_ref_mut(cs).as_mut() {
+            led.toggle();
+        }
+    });
+}
+
+#[qingke_rt::entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+
+    let mut rcc = p.RCC.constrain();
+    let clocks = rcc.config.freeze();
+
+    let d = p.GPIOD.split(&mut rcc);
+    let led = d.pd6.into_push_pull_output();
+
+    // Two updates per second, one for each LED edge
+    let mut timer = Timer::new(p.TIM2, &mut rcc, &clocks);
+    timer.start(2.Hz());
+    timer.listen(Event::Update);
+
+    critical_section::with(|cs| {
+        TIMER.borrow_ref_mut(cs).replace(timer);
+        LED.borrow_ref_mut(cs).replace(led);
+    });
+
+    unsafe { qingke::pfic::enable_interrupt(pac::Interrupt::TIM2 as u8) };
+
+    loop {
+        qingke::riscv::asm::wfi();
+    }
+}
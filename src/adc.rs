@@ -0,0 +1,486 @@
+//! Analog to Digital Converter (ADC)
+//!
+//! The CH32V003 ADC has a 10 bit resolution, conversions return 0 to 1023. Pins are
+//! converted in [`Analog`](crate::gpio::Analog) mode, they implement
+//! `adc::Channel<ADC1>` with their channel number.
+//!
+//! The internal reference voltage is channel 8, see [`Vrefint`] and [`Adc::read_vref`].
+//! Unlike the CH32V103/V20x the CH32V003 has no temperature sensor channel, and the
+//! reference is always connected: there is no TSVREFE bit.
+
+use embedded_hal_02::adc::{Channel, OneShot};
+
+use core::sync::atomic::{self, Ordering};
+
+use crate::{
+    dma,
+    pac::ADC1,
+    rcc::{Clocks, Enable, Rcc, Reset},
+};
+
+/// Largest conversion result
+pub const MAX_VALUE: u16 = 0x3ff;
+
+/// Typical voltage of the internal reference, in millivolts
+pub const VREFINT_MV: u32 = 1200;
+
+/// Internal reference voltage channel
+pub struct Vrefint;
+
+impl Channel<ADC1> for Vrefint {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        8
+    }
+}
+
+/// ADC error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Bus error during a DMA transfer
+    Dma,
+}
+
+/// Number of ADC clock cycles a channel is sampled for before being converted
+///
+/// During the sample time the source charges the ADC's sampling capacitor. A high source
+/// impedance charges it slowly and needs a longer sample time to read accurately, at the
+/// cost of a lower conversion rate. Sources buffered by an op-amp can use the shortest ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SampleTime {
+    Cycles3 = 0b000,
+    Cycles9 = 0b001,
+    Cycles15 = 0b010,
+    Cycles30 = 0b011,
+    /// Used for every channel by [`Adc::new`]
+    #[default]
+    Cycles43 = 0b100,
+    Cycles57 = 0b101,
+    Cycles73 = 0b110,
+    Cycles241 = 0b111,
+}
+
+/// Event starting regular conversions (EXTSEL)
+///
+/// Conversions start on the rising edge of the event, the polarity can't be configured.
+/// The timers generate these events once set up through the PAC, e.g. TRGO on update
+/// with `ctlr2.mms` = 0b010.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TriggerSource {
+    Tim1Trgo = 0b000,
+    Tim1Cc1 = 0b001,
+    Tim1Cc2 = 0b010,
+    Tim2Trgo = 0b011,
+    Tim2Cc1 = 0b100,
+    Tim2Cc2 = 0b101,
+    /// Started by the software, as soon as a conversion is requested
+    #[default]
+    Software = 0b111,
+}
+
+/// Event starting injected conversions (JEXTSEL)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InjectedTrigger {
+    Tim1Cc3 = 0b000,
+    Tim1Cc4 = 0b001,
+    Tim2Cc3 = 0b010,
+    Tim2Cc4 = 0b011,
+    /// Started by [`Adc::inject`]
+    #[default]
+    Software = 0b111,
+}
+
+/// ADC1, converting one channel at a time
+pub struct Adc {
+    adc: ADC1,
+    vdda_mv: u16,
+}
+
+impl Adc {
+    /// Enable the ADC and calibrate it
+    ///
+    /// The ADC clock is set by [`Config::adc_pre`](crate::rcc::Config::adc_pre), every
+    /// prescaler keeps it within the 24MHz maximum.
+    pub fn new(adc: ADC1, rcc: &mut Rcc, clocks: &Clocks) -> Self {
+        ADC1::enable(&mut rcc.apb2);
+        ADC1::reset(&mut rcc.apb2);
+
+        // Regular and injected conversions are started by SWSTART and JSWSTART, which
+        // require the external triggers with the software sources
+        adc.ctlr2.write(|w| {
+            w.adon()
+                .set_bit()
+                .extsel()
+                .variant(TriggerSource::Software as u8)
+                .exttrig()
+                .set_bit()
+                .jextsel()
+                .variant(InjectedTrigger::Software as u8)
+                .jexttrig()
+                .set_bit()
+        });
+
+        // Wait at least 1us for the ADC to power up
+        qingke::riscv::asm::delay(clocks.hclk().raw() / 2_000_000 + 1);
+
+        let mut adc = Self { adc, vdda_mv: 3300 };
+        for channel in 0..10 {
+            adc.set_channel_sample_time(channel, SampleTime::default());
+        }
+        // The internal reference has a high impedance
+        adc.set_channel_sample_time(Vrefint::channel(), SampleTime::Cycles241);
+        adc.calibrate();
+        adc
+    }
+
+    /// Run the self calibration, which the ADC needs once after being powered on
+    pub fn calibrate(&mut self) {
+        self.adc.ctlr2.modify(|_, w| w.rstcal().set_bit());
+        while self.adc.ctlr2.read().rstcal().bit_is_set() {}
+
+        self.adc.ctlr2.modify(|_, w| w.cal().set_bit());
+        while self.adc.ctlr2.read().cal().bit_is_set() {}
+    }
+
+    /// Sample the channel of `pin` for `time` in the following conversions
+    pub fn set_sample_time<PIN: Channel<ADC1, ID = u8>>(&mut self, _pin: &PIN, time: SampleTime) {
+        self.set_channel_sample_time(PIN::channel(), time);
+    }
+
+    /// Channels 0 to 9 are in SAMPTR2, channels 10 to 15 in SAMPTR1, 3 bits each
+    fn set_channel_sample_time(&mut self, channel: u8, time: SampleTime) {
+        let time = time as u32;
+        // NOTE(unsafe) the fields of all channels are valid for any 3 bit value
+        unsafe {
+            if channel < 10 {
+                let offset = channel * 3;
+                self.adc
+                    .samptr2_charge2
+                    .modify(|r, w| w.bits((r.bits() & !(0b111 << offset)) | (time << offset)));
+            } else {
+                let offset = (channel - 10) * 3;
+                self.adc
+                    .samptr1_charge1
+                    .modify(|r, w| w.bits((r.bits() & !(0b111 << offset)) | (time << offset)));
+            }
+        }
+    }
+
+    /// Select the event starting the following regular conversions
+    ///
+    /// With a timer event [`Self::read`] and [`Self::sequence`] wait for the next event
+    /// instead of starting right away, sampling at the rate of the timer. A sequence is
+    /// converted in full on each event, with its results moved by DMA.
+    pub fn set_external_trigger(&mut self, source: TriggerSource) {
+        self.adc
+            .ctlr2
+            .modify(|_, w| w.swstart().clear_bit().extsel().variant(source as u8));
+    }
+
+    /// Convert the voltage on `pin` once, blocking until the result is ready
+    pub fn read<PIN: Channel<ADC1, ID = u8>>(&mut self, _pin: &mut PIN) -> u16 {
+        self.convert(PIN::channel())
+    }
+
+    /// Single conversion of `channel`
+    fn convert(&mut self, channel: u8) -> u16 {
+        self.adc.rsqr1.modify(|_, w| w.l().variant(0));
+        self.adc.rsqr3.modify(|_, w| w.sq1().variant(channel));
+
+        self.adc.ctlr2.modify(|_, w| w.swstart().set_bit());
+        while self.adc.statr.read().eoc().bit_is_clear() {}
+
+        // Reading the data clears EOC
+        self.adc.rdatar.read().data().bits() as u16
+    }
+
+    /// Measure the supply voltage VDDA in millivolts, from a conversion of [`Vrefint`]
+    ///
+    /// The ADC converts relative to VDDA, so a known reference converting to a lower value
+    /// means a higher supply. The result is only as accurate as the reference, which varies
+    /// between chips, see the datasheet.
+    ///
+    /// The measurement is also used by [`Self::to_millivolts`] from then on, call this
+    /// again from time to time to follow a drifting supply, e.g. a discharging battery.
+    pub fn read_vref(&mut self) -> u16 {
+        let raw = self.read(&mut Vrefint).max(1) as u32;
+        self.vdda_mv = (VREFINT_MV * MAX_VALUE as u32 / raw) as u16;
+        self.vdda_mv
+    }
+
+    /// Set the supply voltage VDDA used by [`Self::to_millivolts`], when it is known to be
+    /// more accurate than [`Self::read_vref`], e.g. from a precise regulator
+    ///
+    /// Defaults to 3300mV.
+    pub fn set_vdda(&mut self, millivolts: u16) {
+        self.vdda_mv = millivolts;
+    }
+
+    /// Convert a raw conversion result to millivolts, using the last measured or set VDDA
+    pub fn to_millivolts(&self, raw: u16) -> u16 {
+        (raw as u32 * self.vdda_mv as u32 / MAX_VALUE as u32) as u16
+    }
+
+    /// Watch the conversions of the channel of `pin` for results outside `low..=high`
+    ///
+    /// Every regular conversion of that channel is checked, which suits continuous or timer
+    /// triggered conversions best. A result outside the window sets the flag read by
+    /// [`Self::is_watchdog_pending`], and raises the `ADC` interrupt after
+    /// [`Self::listen_watchdog`]. The thresholds are 10 bit values.
+    pub fn set_watchdog<PIN: Channel<ADC1, ID = u8>>(&mut self, _pin: &PIN, low: u16, high: u16) {
+        self.adc.wdltr.write(|w| w.lt().variant(low.min(MAX_VALUE)));
+        self.adc
+            .wdhtr
+            .write(|w| w.ht().variant(high.min(MAX_VALUE)));
+        self.adc.ctlr1.modify(|_, w| {
+            w.awdch()
+                .variant(PIN::channel())
+                .awdsgl()
+                .set_bit()
+                .awden()
+                .set_bit()
+        });
+    }
+
+    /// Stop watching the conversions
+    pub fn disable_watchdog(&mut self) {
+        self.adc
+            .ctlr1
+            .modify(|_, w| w.awden().clear_bit().awdie().clear_bit());
+    }
+
+    /// Raise the `ADC` interrupt when the watched channel leaves its window
+    pub fn listen_watchdog(&mut self) {
+        self.adc.ctlr1.modify(|_, w| w.awdie().set_bit());
+    }
+
+    /// Stop raising the `ADC` interrupt for the watchdog
+    pub fn unlisten_watchdog(&mut self) {
+        self.adc.ctlr1.modify(|_, w| w.awdie().clear_bit());
+    }
+
+    /// A conversion of the watched channel was outside the window
+    pub fn is_watchdog_pending(&self) -> bool {
+        self.adc.statr.read().awd().bit_is_set()
+    }
+
+    /// Clear the watchdog flag, which also ends the interrupt request
+    pub fn clear_watchdog(&mut self) {
+        // NOTE(unsafe) the other flags are unaffected by writing 1
+        self.adc.statr.write(|w| unsafe { w.bits(!(1 << 0)) });
+    }
+
+    /// Set up the injected group to convert `channels` (1 to 4 channel numbers), and start
+    /// it right away with the software trigger
+    ///
+    /// Injected conversions take priority: a triggered group interrupts the regular
+    /// conversion in progress, which is resumed afterwards. With the software trigger this
+    /// blocks until all results are ready, with a timer trigger [`Self::is_injected_done`]
+    /// tells when they are. Each result is read with [`Self::read_injected`].
+    ///
+    /// # Panics
+    ///
+    /// If `channels` is empty or has more than 4 entries.
+    pub fn inject(&mut self, channels: &[u8]) {
+        assert!((1..=4).contains(&channels.len()));
+
+        // A group of n channels is taken from the last n of JSQ1 to JSQ4
+        let first = 4 - channels.len();
+        let mut isqr = ((channels.len() - 1) as u32) << 20;
+        for (i, &channel) in channels.iter().enumerate() {
+            isqr |= ((channel & 0x1f) as u32) << (5 * (first + i));
+        }
+        // NOTE(unsafe) any channel number and sequence length is valid
+        unsafe { self.adc.isqr.write(|w| w.bits(isqr)) };
+
+        // All the injected channels are only converted in scan mode
+        if channels.len() > 1 {
+            self.adc.ctlr1.modify(|_, w| w.scan().set_bit());
+        }
+
+        // NOTE(unsafe) the other flags are unaffected by writing 1
+        self.adc.statr.write(|w| unsafe { w.bits(!(1 << 2)) });
+
+        if self.adc.ctlr2.read().jextsel().bits() == InjectedTrigger::Software as u8 {
+            self.adc.ctlr2.modify(|_, w| w.jswstart().set_bit());
+            while !self.is_injected_done() {}
+        }
+    }
+
+    /// Select the event starting the injected group
+    pub fn set_injected_trigger(&mut self, trigger: InjectedTrigger) {
+        self.adc
+            .ctlr2
+            .modify(|_, w| w.jswstart().clear_bit().jextsel().variant(trigger as u8));
+    }
+
+    /// Subtract `offset` from the result of the `index`th channel of the injected group
+    ///
+    /// # Panics
+    ///
+    /// If `index` isn't 0 to 3.
+    pub fn set_injected_offset(&mut self, index: usize, offset: u16) {
+        assert!(index < 4);
+        let offset = offset.min(MAX_VALUE);
+        match index {
+            0 => self.adc.iofr1.write(|w| w.joffset1().variant(offset)),
+            1 => self.adc.iofr2.write(|w| w.joffset2().variant(offset)),
+            2 => self.adc.iofr3.write(|w| w.joffset3().variant(offset)),
+            _ => self.adc.iofr4.write(|w| w.joffset4().variant(offset)),
+        }
+    }
+
+    /// All the channels of the injected group have been converted (JEOC)
+    ///
+    /// The flag is cleared by [`Self::inject`].
+    pub fn is_injected_done(&self) -> bool {
+        self.adc.statr.read().jeoc().bit_is_set()
+    }
+
+    /// Result of the `index`th channel of the injected group, minus its offset
+    ///
+    /// The result is negative when the conversion was below the offset.
+    ///
+    /// # Panics
+    ///
+    /// If `index` isn't 0 to 3.
+    pub fn read_injected(&self, index: usize) -> i16 {
+        assert!(index < 4);
+        let data = match index {
+            0 => self.adc.idatar1.read().idata().bits(),
+            1 => self.adc.idatar2.read().idata().bits(),
+            2 => self.adc.idatar3.read().idata().bits(),
+            _ => self.adc.idatar4.read().idata().bits(),
+        };
+        data as i16
+    }
+
+    /// Convert each of `channels` once in a single scan, storing the results in `buffer`
+    /// with DMA channel 1
+    ///
+    /// The channels are taken in order and converted back to back, which samples them much
+    /// closer in time than separate conversions. Channel numbers are given by
+    /// `Channel::channel()`, e.g. `<PD4<Analog> as Channel<ADC1>>::channel()`, and may
+    /// repeat. Blocks until the DMA transfer has completed.
+    ///
+    /// # Panics
+    ///
+    /// If `channels` is empty or has more than 16 entries, or `buffer` is shorter.
+    pub fn sequence(
+        &mut self,
+        dma: &mut dma::C1,
+        channels: &[u8],
+        buffer: &mut [u16],
+    ) -> Result<(), Error> {
+        assert!((1..=16).contains(&channels.len()));
+        assert!(buffer.len() >= channels.len());
+
+        // SQ1 to SQ6 are in RSQR3, SQ7 to SQ12 in RSQR2 and SQ13 to SQ16 in RSQR1, 5 bits each
+        let mut sqr = [0u32; 3];
+        for (i, &channel) in channels.iter().enumerate() {
+            sqr[i / 6] |= ((channel & 0x1f) as u32) << (5 * (i % 6));
+        }
+        let length = (channels.len() - 1) as u32;
+        // NOTE(unsafe) any channel number and sequence length is valid
+        unsafe {
+            self.adc.rsqr3.write(|w| w.bits(sqr[0]));
+            self.adc.rsqr2.write(|w| w.bits(sqr[1]));
+            self.adc.rsqr1.write(|w| w.bits(sqr[2] | (length << 20)));
+        }
+
+        dma.stop();
+        dma.configure(
+            dma::Direction::PeripheralToMemory,
+            dma::WordSize::Bits16,
+            false,
+        );
+        dma.set_peripheral_address(self.adc.rdatar.as_ptr() as u32, false);
+        dma.set_memory_address(buffer.as_mut_ptr() as u32, true);
+        dma.set_transfer_length(channels.len());
+        dma.start();
+
+        // The injected group may need SCAN as well, see Self::inject
+        let scan = self.adc.ctlr1.read().scan().bit();
+        self.adc.ctlr1.modify(|_, w| w.scan().set_bit());
+        self.adc.ctlr2.modify(|_, w| w.dma().set_bit());
+        self.adc.ctlr2.modify(|_, w| w.swstart().set_bit());
+
+        while !dma.is_complete() && !dma.has_error() {}
+        let failed = dma.has_error();
+        dma.stop();
+
+        // Don't let the compiler reorder buffer accesses before the transfer has completed
+        atomic::compiler_fence(Ordering::Acquire);
+
+        self.adc.ctlr2.modify(|_, w| w.dma().clear_bit());
+        self.adc.ctlr1.modify(|_, w| w.scan().bit(scan));
+        if failed {
+            Err(Error::Dma)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Keep converting the channel of `pin`, see [`ContinuousAdc::latest`]
+    ///
+    /// Returns once the first conversion is done, so the latest value is always valid.
+    pub fn start_continuous<PIN: Channel<ADC1, ID = u8>>(self, _pin: &mut PIN) -> ContinuousAdc {
+        self.adc.rsqr1.modify(|_, w| w.l().variant(0));
+        self.adc
+            .rsqr3
+            .modify(|_, w| w.sq1().variant(PIN::channel()));
+
+        self.adc.ctlr2.modify(|_, w| w.cont().set_bit());
+        self.adc.ctlr2.modify(|_, w| w.swstart().set_bit());
+        while self.adc.statr.read().eoc().bit_is_clear() {}
+
+        ContinuousAdc { adc: self }
+    }
+
+    /// Power the ADC down and return the peripheral
+    pub fn release(self) -> ADC1 {
+        self.adc.ctlr2.modify(|_, w| w.adon().clear_bit());
+        self.adc
+    }
+}
+
+/// ADC1 converting a single channel over and over, see [`Adc::start_continuous`]
+pub struct ContinuousAdc {
+    adc: Adc,
+}
+
+impl ContinuousAdc {
+    /// Result of the last completed conversion
+    ///
+    /// The data register only ever holds complete results and is read at once, so this is
+    /// safe to call at any time, also from interrupts. Conversions finishing between two
+    /// calls are overwritten.
+    pub fn latest(&self) -> u16 {
+        self.adc.adc.rdatar.read().data().bits() as u16
+    }
+
+    /// Stop after the conversion in progress and go back to single conversions
+    pub fn stop(self) -> Adc {
+        let adc = self.adc;
+        adc.adc.ctlr2.modify(|_, w| w.cont().clear_bit());
+
+        // A conversion takes at most 252 ADC clock cycles, or 2016 HCLK cycles with the
+        // largest prescaler. Drop its result so it isn't taken for the next one.
+        qingke::riscv::asm::delay(1008);
+        let _ = adc.adc.rdatar.read();
+        adc
+    }
+}
+
+impl<WORD, PIN> OneShot<ADC1, WORD, PIN> for Adc
+where
+    WORD: From<u16>,
+    PIN: Channel<ADC1, ID = u8>,
+{
+    type Error = ();
+
+    fn read(&mut self, pin: &mut PIN) -> nb::Result<WORD, Self::Error> {
+        Ok(Adc::read(self, pin).into())
+    }
+}
@@ -0,0 +1,139 @@
+//! Alternate Function I/O (AFIO)
+//!
+//! Remapping of peripheral pins and the EXTI line multiplexer. The AFIO clock is enabled once
+//! by [`AfioExt::constrain`], drivers that remap their pins take `&mut` access to [`PCFR`].
+//!
+//! Unlike the CH32V103/F103 family, the CH32V003 AFIO has no event control register (ECR):
+//! the core's event output can't be routed to a pin. Toggle a spare GPIO with a single
+//! BSHR write to mark events for a scope instead.
+
+use crate::pac::{afio, AFIO};
+use crate::rcc::{Enable, Rcc, Reset};
+
+/// Extension trait to constrain the AFIO peripheral
+pub trait AfioExt {
+    /// Enable the AFIO clock and split the peripheral into its registers
+    fn constrain(self, rcc: &mut Rcc) -> Parts;
+}
+
+impl AfioExt for AFIO {
+    fn constrain(self, rcc: &mut Rcc) -> Parts {
+        AFIO::enable(&mut rcc.apb2);
+        AFIO::reset(&mut rcc.apb2);
+
+        Parts {
+            pcfr: PCFR { _0: () },
+            exticr: EXTICR { _0: () },
+        }
+    }
+}
+
+/// AFIO registers
+pub struct Parts {
+    /// Remap register
+    pub pcfr: PCFR,
+    /// External interrupt line multiplexer
+    pub exticr: EXTICR,
+}
+
+/// Remap register (AFIO_PCFR1)
+pub struct PCFR {
+    _0: (),
+}
+
+impl PCFR {
+    #[inline(always)]
+    fn pcfr(&mut self) -> &afio::PCFR {
+        // NOTE(unsafe) this proxy grants exclusive access to this register
+        unsafe { &(*AFIO::ptr()).pcfr }
+    }
+
+    /// Select the I2C1 pins, 0b00 to 0b11 (I2C1REMAP1 and I2C1_RM)
+    pub fn set_i2c1_remap(&mut self, remap: u8) {
+        self.pcfr().modify(|_, w| {
+            w.i2c1remap1()
+                .bit(remap & 0b10 != 0)
+                .i2c1rm()
+                .bit(remap & 0b01 != 0)
+        });
+    }
+
+    /// Select the USART1 pins, 0b00 to 0b11 (USART1REMAP1 and USART1_RM)
+    pub fn set_usart1_remap(&mut self, remap: u8) {
+        self.pcfr().modify(|_, w| {
+            w.usart1remap1()
+                .bit(remap & 0b10 != 0)
+                .usart1rm()
+                .bit(remap & 0b01 != 0)
+        });
+    }
+
+    /// Select the SPI1 pins (SPI1_RM)
+    pub fn set_spi1_remap(&mut self, remap: bool) {
+        self.pcfr().modify(|_, w| w.spi1rm().bit(remap));
+    }
+
+    /// Select the TIM1 pins, 0b00 to 0b11 (TIM1_RM)
+    pub fn set_tim1_remap(&mut self, remap: u8) {
+        self.pcfr()
+            .modify(|_, w| unsafe { w.tim1rm().bits(remap & 0b11) });
+    }
+
+    /// Select the TIM2 pins, 0b00 to 0b11 (TIM2_RM)
+    pub fn set_tim2_remap(&mut self, remap: u8) {
+        self.pcfr()
+            .modify(|_, w| unsafe { w.tim2rm().bits(remap & 0b11) });
+    }
+
+    /// Current TIM1 pin selection, 0b00 to 0b11 (TIM1_RM)
+    pub fn tim1_remap(&mut self) -> u8 {
+        self.pcfr().read().tim1rm().bits()
+    }
+
+    /// Current TIM2 pin selection, 0b00 to 0b11 (TIM2_RM)
+    pub fn tim2_remap(&mut self) -> u8 {
+        self.pcfr().read().tim2rm().bits()
+    }
+
+    /// Route TIM1 channel 1 to the LSI for calibration (TIM1_IREMAP)
+    pub fn set_tim1_lsi_remap(&mut self, remap: bool) {
+        self.pcfr().modify(|_, w| w.tim1_iremap().bit(remap));
+    }
+
+    /// Use PA1 and PA2 for the external oscillator instead of GPIO (PA12_RM)
+    pub fn set_pa12_remap(&mut self, remap: bool) {
+        self.pcfr().modify(|_, w| w.pa12rm().bit(remap));
+    }
+
+    /// Disable the single wire debug interface to use its pin as GPIO (SWCFG = 0b100)
+    ///
+    /// The CH32V003 has no JTAG and no PA13/PA14, its only debug pin is SWIO on PD1. Until
+    /// this is called PD1 stays connected to the debug module and writes to it have no effect.
+    /// The other SWCFG encodings (0b000 to 0b011) keep SWD enabled.
+    ///
+    /// **Warning**: once disabled the debugger can't attach to the running firmware anymore.
+    /// If this runs early after reset, the chip can only be reflashed by holding it in reset
+    /// or by a power cycle with the programmer's unlock procedure. Consider a delay before
+    /// calling this during development.
+    pub fn disable_swj(&mut self) {
+        self.pcfr().modify(|_, w| unsafe { w.swcfg().bits(0b100) });
+    }
+}
+
+/// External interrupt configuration register (AFIO_EXTICR)
+pub struct EXTICR {
+    _0: (),
+}
+
+impl EXTICR {
+    /// Route the pin `line` of `port` (0 for A, 2 for C, 3 for D) to EXTI line `line`
+    pub(crate) fn set_source(&mut self, line: u8, port: u8) {
+        let offset = line * 2;
+        // NOTE(unsafe) this proxy grants exclusive access to this register
+        unsafe {
+            (*AFIO::ptr()).exticr.modify(|r, w| {
+                w.bits((r.bits() & !(0b11 << offset)) | (((port & 0b11) as u32) << offset))
+            });
+        }
+    }
+}
@@ -1,9 +1,109 @@
-//! Simple busy-loop delay provider
+//! Blocking delay providers
+//!
+//! [`SysDelay`] counts HCLK cycles with the SysTick counter of the core, [`CycleDelay`] is
+//! a busy loop for when the SysTick is used otherwise. A general purpose timer can time
+//! delays as well, see [`Timer::delay`](crate::timer::Timer::delay).
 
 use fugit::HertzU32;
 
+use crate::pac::SYSTICK;
 use crate::rcc::Clocks;
 
+/// A delay counted by the SysTick timer, at HCLK
+///
+/// The counter runs freely and is shared by consecutive delays, interrupts only lengthen a
+/// delay by the time spent in their handler past its end.
+pub struct SysDelay {
+    systick: SYSTICK,
+    rate: HertzU32,
+}
+
+impl SysDelay {
+    /// Start the SysTick counter, clocked by HCLK (STCLK)
+    pub fn new(systick: SYSTICK, clocks: &Clocks) -> SysDelay {
+        systick
+            .ctlr
+            .write(|w| w.stclk().set_bit().stre().clear_bit().ste().set_bit());
+
+        SysDelay {
+            systick,
+            rate: clocks.hclk(),
+        }
+    }
+
+    /// Stop the counter and return the peripheral
+    pub fn release(self) -> SYSTICK {
+        self.systick.ctlr.modify(|_, w| w.ste().clear_bit());
+        self.systick
+    }
+
+    fn delay_cycles(&mut self, mut cycles: u64) {
+        // The 32 bit counter wraps around, compare elapsed cycles with a wrapping
+        // subtraction and keep each wait well below a full turn
+        const MAX_CYCLES: u64 = 1 << 31;
+
+        while cycles != 0 {
+            let chunk = cycles.min(MAX_CYCLES) as u32;
+            let start = self.systick.cnt.read().bits();
+            while self.systick.cnt.read().bits().wrapping_sub(start) < chunk {}
+            cycles -= chunk as u64;
+        }
+    }
+}
+
+impl embedded_hal_1::delay::DelayNs for SysDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        let cycles = (ns as u64 * self.rate.to_Hz() as u64).div_ceil(1_000_000_000);
+        self.delay_cycles(cycles);
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        let cycles = (us as u64 * self.rate.to_Hz() as u64).div_ceil(1_000_000);
+        self.delay_cycles(cycles);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        let cycles = (ms as u64 * self.rate.to_Hz() as u64).div_ceil(1_000);
+        self.delay_cycles(cycles);
+    }
+}
+
+impl embedded_hal_02::blocking::delay::DelayUs<u32> for SysDelay {
+    fn delay_us(&mut self, us: u32) {
+        embedded_hal_1::delay::DelayNs::delay_us(self, us);
+    }
+}
+
+impl embedded_hal_02::blocking::delay::DelayUs<u16> for SysDelay {
+    fn delay_us(&mut self, us: u16) {
+        embedded_hal_1::delay::DelayNs::delay_us(self, us as _);
+    }
+}
+
+impl embedded_hal_02::blocking::delay::DelayUs<u8> for SysDelay {
+    fn delay_us(&mut self, us: u8) {
+        embedded_hal_1::delay::DelayNs::delay_us(self, us as _);
+    }
+}
+
+impl embedded_hal_02::blocking::delay::DelayMs<u32> for SysDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        embedded_hal_1::delay::DelayNs::delay_ms(self, ms);
+    }
+}
+
+impl embedded_hal_02::blocking::delay::DelayMs<u16> for SysDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        embedded_hal_1::delay::DelayNs::delay_ms(self, ms as _);
+    }
+}
+
+impl embedded_hal_02::blocking::delay::DelayMs<u8> for SysDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        embedded_hal_1::delay::DelayNs::delay_ms(self, ms as _);
+    }
+}
+
 pub struct CycleDelay {
     rate: HertzU32,
 }
@@ -0,0 +1,217 @@
+//! Direct Memory Access (DMA)
+//!
+//! DMA1 has 7 channels, each hardwired to the requests of a few peripherals:
+//!
+//! | Channel | Requests                     |
+//! |---------|------------------------------|
+//! | 1       | ADC1, TIM2_CH3               |
+//! | 2       | SPI1_RX, TIM1_CH1, TIM2_UP   |
+//! | 3       | SPI1_TX, TIM1_CH2            |
+//! | 4       | USART1_TX, TIM1_CH4          |
+//! | 5       | USART1_RX, TIM1_UP, TIM2_CH1 |
+//! | 6       | I2C1_TX, TIM1_CH3            |
+//! | 7       | I2C1_RX, TIM2_CH2, TIM2_CH4  |
+
+use core::sync::atomic::{self, Ordering};
+
+use crate::{
+    pac::{dma1, DMA1},
+    rcc::{Enable, Rcc},
+};
+
+/// Extension trait to split the DMA peripheral into independent channels
+pub trait DmaExt {
+    type Channels;
+
+    fn split(self, rcc: &mut Rcc) -> Self::Channels;
+}
+
+/// Direction of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Read from the peripheral, write to memory
+    PeripheralToMemory,
+    /// Read from memory, write to the peripheral
+    MemoryToPeripheral,
+}
+
+/// Size of a single item moved by the DMA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordSize {
+    Bits8 = 0b00,
+    Bits16 = 0b01,
+    Bits32 = 0b10,
+}
+
+/// Peripheral side of a DMA transfer
+pub trait TransferPayload {
+    type Error;
+
+    /// Whether the peripheral is done with the buffer
+    fn is_done(&self) -> bool;
+
+    /// Wrap up the transfer once [`Self::is_done`] returns true
+    fn finish(&mut self) -> Result<(), Self::Error>;
+}
+
+/// An ongoing DMA transfer, owning the buffer until it has completed
+pub struct Transfer<BUF, PAYLOAD> {
+    buffer: BUF,
+    payload: PAYLOAD,
+}
+
+impl<BUF, PAYLOAD> Transfer<BUF, PAYLOAD>
+where
+    PAYLOAD: TransferPayload,
+{
+    pub(crate) fn new(buffer: BUF, payload: PAYLOAD) -> Self {
+        Self { buffer, payload }
+    }
+
+    /// Completion flag, true once [`Self::wait`] won't block anymore
+    pub fn is_done(&self) -> bool {
+        self.payload.is_done()
+    }
+
+    /// Block until the transfer has completed, returning its result and the resources
+    pub fn wait(mut self) -> (Result<(), PAYLOAD::Error>, BUF, PAYLOAD) {
+        while !self.payload.is_done() {}
+
+        // Don't let the compiler reorder buffer accesses before the transfer has completed
+        atomic::compiler_fence(Ordering::Acquire);
+
+        let result = self.payload.finish();
+        (result, self.buffer, self.payload)
+    }
+}
+
+macro_rules! dma_channels {
+    ($(($CX:ident, $chX:ident, $doc:literal, $cfgrX:ident, $cntrX:ident, $paddrX:ident, $maddrX:ident,
+        $tcifX:ident, $teifX:ident, $cgifX:ident),)+) => {
+        /// DMA1 channels
+        pub struct Channels {
+            $(
+                #[doc = $doc]
+                pub $chX: $CX,
+            )+
+        }
+
+        $(
+            #[doc = $doc]
+            pub struct $CX {
+                _0: (),
+            }
+
+            impl $CX {
+                /// Address of the peripheral data register, and whether it is incremented after each item
+                pub fn set_peripheral_address(&mut self, address: u32, inc: bool) {
+                    let dma = self.dma();
+                    dma.$paddrX.write(|w| w.pa().variant(address));
+                    dma.$cfgrX.modify(|_, w| w.pinc().bit(inc));
+                }
+
+                /// Address of the memory buffer, and whether it is incremented after each item
+                pub fn set_memory_address(&mut self, address: u32, inc: bool) {
+                    let dma = self.dma();
+                    dma.$maddrX.write(|w| w.ma().variant(address));
+                    dma.$cfgrX.modify(|_, w| w.minc().bit(inc));
+                }
+
+                /// Number of items to transfer
+                ///
+                /// # Panics
+                ///
+                /// If `len` is greater than 65535, the size of the counter.
+                pub fn set_transfer_length(&mut self, len: usize) {
+                    assert!(len <= u16::MAX as usize);
+                    self.dma().$cntrX.write(|w| w.ndt().variant(len as u16));
+                }
+
+                /// Direction, item size and circular mode, sizes are the same on both sides
+                pub fn configure(&mut self, direction: Direction, size: WordSize, circular: bool) {
+                    self.dma().$cfgrX.modify(|_, w| unsafe {
+                        w.dir()
+                            .bit(direction == Direction::MemoryToPeripheral)
+                            .psize()
+                            .bits(size as u8)
+                            .msize()
+                            .bits(size as u8)
+                            .circ()
+                            .bit(circular)
+                            .mem2mem()
+                            .clear_bit()
+                    });
+                }
+
+                /// Enable or disable the transfer complete interrupt
+                pub fn listen_complete(&mut self, enable: bool) {
+                    self.dma().$cfgrX.modify(|_, w| w.tcie().bit(enable));
+                }
+
+                /// Clear the flags of any previous transfer and enable the channel
+                pub fn start(&mut self) {
+                    self.clear_flags();
+
+                    // Make preceding writes to the buffer visible to the DMA
+                    atomic::compiler_fence(Ordering::Release);
+
+                    self.dma().$cfgrX.modify(|_, w| w.en().set_bit());
+                }
+
+                /// Disable the channel
+                pub fn stop(&mut self) {
+                    self.dma().$cfgrX.modify(|_, w| w.en().clear_bit());
+                    self.clear_flags();
+                }
+
+                /// Whether all items have been transferred
+                pub fn is_complete(&self) -> bool {
+                    self.dma().intfr.read().$tcifX().bit_is_set()
+                }
+
+                /// Whether the transfer was aborted by a bus error
+                pub fn has_error(&self) -> bool {
+                    self.dma().intfr.read().$teifX().bit_is_set()
+                }
+
+                /// Number of items left to transfer
+                pub fn remaining(&self) -> u16 {
+                    self.dma().$cntrX.read().ndt().bits()
+                }
+
+                /// Clear all interrupt flags of this channel
+                pub fn clear_flags(&mut self) {
+                    self.dma().intfcr.write(|w| w.$cgifX().set_bit());
+                }
+
+                #[inline(always)]
+                fn dma(&self) -> &dma1::RegisterBlock {
+                    // NOTE(unsafe) each channel only touches its own registers and flags
+                    unsafe { &*DMA1::ptr() }
+                }
+            }
+        )+
+
+        impl DmaExt for DMA1 {
+            type Channels = Channels;
+
+            fn split(self, rcc: &mut Rcc) -> Channels {
+                DMA1::enable(&mut rcc.ahb);
+
+                Channels {
+                    $($chX: $CX { _0: () },)+
+                }
+            }
+        }
+    };
+}
+
+dma_channels! {
+    (C1, ch1, "Channel 1", cfgr1, cntr1, paddr1, maddr1, tcif1, teif1, cgif1),
+    (C2, ch2, "Channel 2", cfgr2, cntr2, paddr2, maddr2, tcif2, teif2, cgif2),
+    (C3, ch3, "Channel 3", cfgr3, cntr3, paddr3, maddr3, tcif3, teif3, cgif3),
+    (C4, ch4, "Channel 4", cfgr4, cntr4, paddr4, maddr4, tcif4, teif4, cgif4),
+    (C5, ch5, "Channel 5", cfgr5, cntr5, paddr5, maddr5, tcif5, teif5, cgif5),
+    (C6, ch6, "Channel 6", cfgr6, cntr6, paddr6, maddr6, tcif6, teif6, cgif6),
+    (C7, ch7, "Channel 7", cfgr7, cntr7, paddr7, maddr7, tcif7, teif7, cgif7),
+}
@@ -18,6 +18,11 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
         Pin::new()
     }
 
+    /// Configures the pin to operate in alternate push pull mode, same as [`Self::into_alternate`]
+    pub fn into_alternate_push_pull(self) -> Pin<P, N, Alternate<PushPull>> {
+        self.into_alternate()
+    }
+
     /// Configures the pin to operate in alternate open drain mode
     pub fn into_alternate_open_drain(self) -> Pin<P, N, Alternate<OpenDrain>> {
         self.into_alternate()
@@ -26,18 +31,27 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
     }
 
     /// Configures the pin to operate as a floating input pin
+    ///
+    /// This also turns an output back into a high impedance input. The configuration is
+    /// switched before the output data bit is cleared, so a pin driven high is released
+    /// rather than briefly driven low, and the bit is back to its reset value afterwards.
     pub fn into_floating_input(mut self) -> Pin<P, N, Input<Floating>> {
         self.mode::<Input<Floating>>();
+        self._set_low();
         Pin::new()
     }
 
     /// Configures the pin to operate as a pulled down input pin
+    ///
+    /// The internal pull-down is enabled, no external resistor is needed.
     pub fn into_pull_down_input(mut self) -> Pin<P, N, Input<PullDown>> {
         self.mode::<Input<PullDown>>();
         Pin::new()
     }
 
     /// Configures the pin to operate as a pulled up input pin
+    ///
+    /// The internal pull-up is enabled, e.g. for a button connecting the pin to ground.
     pub fn into_pull_up_input(mut self) -> Pin<P, N, Input<PullUp>> {
         self.mode::<Input<PullUp>>();
         Pin::new()
@@ -45,13 +59,21 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
 
     /// Configures the pin to operate as an open drain output pin
     /// Initial state will be low.
+    ///
+    /// The internal pull resistors are only connected in input mode, so a released open
+    /// drain output floats unless the line has an external pull-up. There is no open drain
+    /// with pull-up mode in the hardware; for a weak internally pulled bus (e.g. 1-Wire with
+    /// short wires) switch between [`Self::into_pull_up_input`] for high and a low output,
+    /// see [`DynamicPin`](super::DynamicPin) or [`Self::with_open_drain_output_in_state`].
     pub fn into_open_drain_output(mut self) -> Pin<P, N, Output<OpenDrain>> {
+        self._set_low();
         self.mode::<Output<OpenDrain>>();
         Pin::new()
     }
 
     /// Configures the pin to operate as an open-drain output pin.
     /// `initial_state` specifies whether the pin should be initially high or low.
+    /// The level is written to BSHR before the pin is switched to output, so it never glitches.
     pub fn into_open_drain_output_in_state(
         mut self,
         initial_state: PinState,
@@ -71,6 +93,7 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
 
     /// Configures the pin to operate as an push-pull output pin.
     /// `initial_state` specifies whether the pin should be initially high or low.
+    /// The level is written to BSHR before the pin is switched to output, so it never glitches.
     pub fn into_push_pull_output_in_state(
         mut self,
         initial_state: PinState,
@@ -80,36 +103,158 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
         Pin::new()
     }
 
+    /// Configures the pin to operate as an push pull output pin with the given slew rate.
+    /// Initial state will be low.
+    pub fn into_push_pull_output_with_speed(self, speed: Speed) -> Pin<P, N, Output<PushPull>> {
+        self.into_push_pull_output().set_speed(speed)
+    }
+
+    /// Configures the pin to operate as an open drain output pin with the given slew rate.
+    /// Initial state will be low.
+    pub fn into_open_drain_output_with_speed(self, speed: Speed) -> Pin<P, N, Output<OpenDrain>> {
+        self.into_open_drain_output().set_speed(speed)
+    }
+
     /// Configures the pin to operate as an analog input pin
+    ///
+    /// Pins connected to the ADC implement `adc::Channel<ADC1>` in this mode.
     pub fn into_analog(mut self) -> Pin<P, N, Analog> {
         self.mode::<Analog>();
         Pin::new()
     }
 
-    // TODO: into_dynamic
+    /// Temporarily configures this pin as a floating input, restoring the mode after `f` returns
+    pub fn with_floating_input<R>(
+        &mut self,
+        f: impl FnOnce(&mut Pin<P, N, Input<Floating>>) -> R,
+    ) -> R {
+        self.with_mode(f)
+    }
+
+    /// Temporarily configures this pin as a pulled up input, restoring the mode after `f` returns
+    pub fn with_pull_up_input<R>(
+        &mut self,
+        f: impl FnOnce(&mut Pin<P, N, Input<PullUp>>) -> R,
+    ) -> R {
+        self.with_mode(f)
+    }
+
+    /// Temporarily configures this pin as a pulled down input, restoring the mode after `f` returns
+    pub fn with_pull_down_input<R>(
+        &mut self,
+        f: impl FnOnce(&mut Pin<P, N, Input<PullDown>>) -> R,
+    ) -> R {
+        self.with_mode(f)
+    }
+
+    /// Temporarily configures this pin as a push pull output, restoring the mode after `f` returns
+    ///
+    /// The output level is left as it was, use [`Self::with_push_pull_output_in_state`] to
+    /// choose it.
+    pub fn with_push_pull_output<R>(
+        &mut self,
+        f: impl FnOnce(&mut Pin<P, N, Output<PushPull>>) -> R,
+    ) -> R {
+        self.with_mode(f)
+    }
+
+    /// Temporarily configures this pin as a push pull output starting at `state`, restoring
+    /// the mode after `f` returns
+    pub fn with_push_pull_output_in_state<R>(
+        &mut self,
+        state: PinState,
+        f: impl FnOnce(&mut Pin<P, N, Output<PushPull>>) -> R,
+    ) -> R {
+        self.with_mode_in_state(Some(state), f)
+    }
+
+    /// Temporarily configures this pin as an open drain output, restoring the mode after `f` returns
+    ///
+    /// The output level is left as it was, use [`Self::with_open_drain_output_in_state`] to
+    /// choose it.
+    pub fn with_open_drain_output<R>(
+        &mut self,
+        f: impl FnOnce(&mut Pin<P, N, Output<OpenDrain>>) -> R,
+    ) -> R {
+        self.with_mode(f)
+    }
+
+    /// Temporarily configures this pin as an open drain output starting at `state`, restoring
+    /// the mode after `f` returns
+    pub fn with_open_drain_output_in_state<R>(
+        &mut self,
+        state: PinState,
+        f: impl FnOnce(&mut Pin<P, N, Output<OpenDrain>>) -> R,
+    ) -> R {
+        self.with_mode_in_state(Some(state), f)
+    }
+
+    /// Run `f` with the pin in mode `M`, then put back the configuration and output bits
+    ///
+    /// The output data bit is written back before the configuration, as it selects the pull
+    /// direction of pulled inputs and the level of outputs.
+    fn with_mode<M: PinMode, R>(&mut self, f: impl FnOnce(&mut Pin<P, N, M>) -> R) -> R {
+        self.with_mode_in_state(None, f)
+    }
+
+    /// Like [`Self::with_mode`], writing `state` to the output data bit after the snapshot
+    /// and before the mode change
+    fn with_mode_in_state<M: PinMode, R>(
+        &mut self,
+        initial: Option<PinState>,
+        f: impl FnOnce(&mut Pin<P, N, M>) -> R,
+    ) -> R {
+        // NOTE(unsafe) atomic reads with no side effects
+        let (cfgr, state) = unsafe {
+            let gpio = &*Gpio::<P>::ptr();
+            let cfgr = (gpio.cfglr.read().bits() >> Self::OFFSET) & 0b1111;
+            let state = if gpio.outdr.read().bits() & (1 << N) == 0 {
+                PinState::Low
+            } else {
+                PinState::High
+            };
+            (cfgr, state)
+        };
+
+        if let Some(initial) = initial {
+            self._set_state(initial);
+        }
+        self.mode::<M>();
+        let result = f(&mut Pin::new());
+
+        self._set_state(state);
+        unsafe {
+            (*Gpio::<P>::ptr()).cfglr.modify(|r, w| {
+                w.bits((r.bits() & !(0b1111 << Self::OFFSET)) | (cfgr << Self::OFFSET))
+            });
+        }
+
+        result
+    }
 
     /// Puts `self` into mode `M`.
     ///
     /// This violates the type state constraints from `MODE`, so callers must
     /// ensure they use this properly.
+    ///
+    /// For pulled inputs the pull direction is selected by the output data bit. The pin is
+    /// floating while that bit is changed, so it is never driven or pulled the wrong way.
     #[inline(always)]
     pub(super) fn mode<M: PinMode>(&mut self) {
-        let cfgr = (M::CNFR << 2) | M::MODER;
-        unsafe {
+        let set_config = |cfgr: u32| unsafe {
             (*Gpio::<P>::ptr()).cfglr.modify(|r, w| {
                 w.bits((r.bits() & !(0b1111 << Self::OFFSET)) | (cfgr << Self::OFFSET))
             });
-            if let Some(odr) = M::ODR {
-                (*Gpio::<P>::ptr())
-                    .outdr
-                    .modify(|r, w| w.bits((r.bits() & !(1 << N)) | ((odr as u32) << N)));
-            }
+        };
+
+        if let Some(odr) = M::ODR {
+            set_config((Input::<Floating>::CNFR << 2) | Input::<Floating>::MODER);
+            self._set_state(if odr { PinState::High } else { PinState::Low });
         }
+        set_config((M::CNFR << 2) | M::MODER);
     }
 }
 
-// TODO: with_mode
-
 /// Marker trait for valid pin modes (type state).
 ///
 /// It can not be implemented by outside types.
@@ -0,0 +1,67 @@
+/// Integrating debouncer for mechanical switches
+///
+/// Each high sample increments a counter and each low sample decrements it, saturating at
+/// `0` and `threshold`. The debounced level only changes once the counter reaches one end,
+/// so a bouncing contact must settle for `threshold` consecutive samples before a change is
+/// reported. Feed it from a periodic timer, a few milliseconds per sample suit most buttons.
+///
+/// ```ignore
+/// let mut debouncer = Debouncer::new(5, true);
+///
+/// // every 2 ms
+/// if debouncer.update(button.is_high()) == Some(false) {
+///     // button pressed
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Debouncer {
+    count: u8,
+    threshold: u8,
+    state: bool,
+}
+
+impl Debouncer {
+    /// New debouncer needing `threshold` stable samples (at least 1), starting at level `initial`
+    pub const fn new(threshold: u8, initial: bool) -> Self {
+        let threshold = if threshold == 0 { 1 } else { threshold };
+        Self {
+            count: if initial { threshold } else { 0 },
+            threshold,
+            state: initial,
+        }
+    }
+
+    /// Feed a raw sample, returning the new level if the debounced level just changed
+    pub fn update(&mut self, sample: bool) -> Option<bool> {
+        if sample {
+            self.count = (self.count + 1).min(self.threshold);
+        } else {
+            self.count = self.count.saturating_sub(1);
+        }
+
+        let state = if self.count == self.threshold {
+            true
+        } else if self.count == 0 {
+            false
+        } else {
+            self.state
+        };
+
+        if state != self.state {
+            self.state = state;
+            Some(state)
+        } else {
+            None
+        }
+    }
+
+    /// Debounced level is high
+    pub fn is_high(&self) -> bool {
+        self.state
+    }
+
+    /// Debounced level is low
+    pub fn is_low(&self) -> bool {
+        !self.state
+    }
+}
@@ -0,0 +1,220 @@
+use super::*;
+
+/// Pin type with dynamic mode
+///
+/// - `P` is port name: `A` for GPIOA, `C` for GPIOC, etc.
+/// - `N` is pin number: from `0` to `7`.
+pub struct DynamicPin<const P: char, const N: u8> {
+    /// Current pin mode
+    pub(crate) mode: Dynamic,
+}
+
+/// Tracks the current pin state for dynamic pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dynamic {
+    InputFloating,
+    InputPullUp,
+    InputPullDown,
+    OutputPushPull,
+    OutputOpenDrain,
+}
+
+/// Error returned when a dynamic pin is used in the wrong mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinModeError {
+    IncorrectMode,
+}
+
+impl Dynamic {
+    pub fn is_input(&self) -> bool {
+        use Dynamic::*;
+        match self {
+            InputFloating | InputPullUp | InputPullDown | OutputOpenDrain => true,
+            OutputPushPull => false,
+        }
+    }
+
+    pub fn is_output(&self) -> bool {
+        use Dynamic::*;
+        match self {
+            InputFloating | InputPullUp | InputPullDown => false,
+            OutputPushPull | OutputOpenDrain => true,
+        }
+    }
+}
+
+impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
+    /// Configures the pin as a pin that can change between input and output without
+    /// changing the type. It starts out as a floating input.
+    pub fn into_dynamic(self) -> DynamicPin<P, N> {
+        self.into_floating_input();
+        DynamicPin::new(Dynamic::InputFloating)
+    }
+}
+
+/// Placeholder mode for the temporary pins used to reconfigure a dynamic pin
+struct Unknown;
+
+impl<const P: char, const N: u8> DynamicPin<P, N> {
+    const fn new(mode: Dynamic) -> Self {
+        Self { mode }
+    }
+
+    /// Current mode of the pin
+    #[inline]
+    pub fn mode(&self) -> Dynamic {
+        self.mode
+    }
+
+    #[inline]
+    pub fn make_pull_up_input(&mut self) {
+        Pin::<P, N, Unknown>::new().into_pull_up_input();
+        self.mode = Dynamic::InputPullUp;
+    }
+
+    #[inline]
+    pub fn make_pull_down_input(&mut self) {
+        Pin::<P, N, Unknown>::new().into_pull_down_input();
+        self.mode = Dynamic::InputPullDown;
+    }
+
+    #[inline]
+    pub fn make_floating_input(&mut self) {
+        Pin::<P, N, Unknown>::new().into_floating_input();
+        self.mode = Dynamic::InputFloating;
+    }
+
+    #[inline]
+    pub fn make_push_pull_output(&mut self) {
+        Pin::<P, N, Unknown>::new().into_push_pull_output();
+        self.mode = Dynamic::OutputPushPull;
+    }
+
+    #[inline]
+    pub fn make_push_pull_output_in_state(&mut self, state: PinState) {
+        Pin::<P, N, Unknown>::new().into_push_pull_output_in_state(state);
+        self.mode = Dynamic::OutputPushPull;
+    }
+
+    #[inline]
+    pub fn make_open_drain_output(&mut self) {
+        Pin::<P, N, Unknown>::new().into_open_drain_output();
+        self.mode = Dynamic::OutputOpenDrain;
+    }
+
+    #[inline]
+    pub fn make_open_drain_output_in_state(&mut self, state: PinState) {
+        Pin::<P, N, Unknown>::new().into_open_drain_output_in_state(state);
+        self.mode = Dynamic::OutputOpenDrain;
+    }
+
+    pub fn set_high(&mut self) -> Result<(), PinModeError> {
+        if self.mode.is_output() {
+            Pin::<P, N, Unknown>::new()._set_high();
+            Ok(())
+        } else {
+            Err(PinModeError::IncorrectMode)
+        }
+    }
+
+    pub fn set_low(&mut self) -> Result<(), PinModeError> {
+        if self.mode.is_output() {
+            Pin::<P, N, Unknown>::new()._set_low();
+            Ok(())
+        } else {
+            Err(PinModeError::IncorrectMode)
+        }
+    }
+
+    pub fn set_state(&mut self, state: PinState) -> Result<(), PinModeError> {
+        match state {
+            PinState::High => self.set_high(),
+            PinState::Low => self.set_low(),
+        }
+    }
+
+    pub fn is_high(&self) -> Result<bool, PinModeError> {
+        self.is_low().map(|b| !b)
+    }
+
+    pub fn is_low(&self) -> Result<bool, PinModeError> {
+        if self.mode.is_input() {
+            Ok(Pin::<P, N, Unknown>::new()._is_low())
+        } else {
+            Err(PinModeError::IncorrectMode)
+        }
+    }
+}
+
+impl<const P: char, const N: u8> fmt::Debug for DynamicPin<P, N> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_fmt(format_args!("P{}{}<{:?}>", P, N, self.mode))
+    }
+}
+
+impl<const P: char, const N: u8> PinExt for DynamicPin<P, N> {
+    type Mode = Dynamic;
+
+    #[inline(always)]
+    fn pin_id(&self) -> u8 {
+        N
+    }
+    #[inline(always)]
+    fn port_id(&self) -> u8 {
+        P as u8 - b'A'
+    }
+}
+
+impl embedded_hal_1::digital::Error for PinModeError {
+    fn kind(&self) -> embedded_hal_1::digital::ErrorKind {
+        embedded_hal_1::digital::ErrorKind::Other
+    }
+}
+
+impl<const P: char, const N: u8> embedded_hal_1::digital::ErrorType for DynamicPin<P, N> {
+    type Error = PinModeError;
+}
+
+impl<const P: char, const N: u8> embedded_hal_1::digital::OutputPin for DynamicPin<P, N> {
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        DynamicPin::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        DynamicPin::set_low(self)
+    }
+}
+
+impl<const P: char, const N: u8> embedded_hal_1::digital::InputPin for DynamicPin<P, N> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        DynamicPin::is_high(self)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        DynamicPin::is_low(self)
+    }
+}
+
+impl<const P: char, const N: u8> embedded_hal_02::digital::v2::OutputPin for DynamicPin<P, N> {
+    type Error = PinModeError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        DynamicPin::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        DynamicPin::set_low(self)
+    }
+}
+
+impl<const P: char, const N: u8> embedded_hal_02::digital::v2::InputPin for DynamicPin<P, N> {
+    type Error = PinModeError;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        DynamicPin::is_high(self)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        DynamicPin::is_low(self)
+    }
+}
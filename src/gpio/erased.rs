@@ -0,0 +1,163 @@
+use super::*;
+
+pub type EPin<MODE> = ErasedPin<MODE>;
+
+/// Fully erased pin
+///
+/// Port and pin number are kept at runtime, so pins of different ports can be stored together.
+/// The typed pin can be recovered with `Pin::try_from`, which checks them.
+///
+/// - `MODE` is one of the pin modes (see [Modes](crate::gpio#modes) section).
+pub struct ErasedPin<MODE> {
+    // Bits 0-3: Pin, Bits 4-7: Port
+    pin_port: u8,
+    _mode: PhantomData<MODE>,
+}
+
+impl<MODE> ErasedPin<MODE> {
+    pub(crate) fn new(port: u8, pin: u8) -> Self {
+        Self {
+            pin_port: (port << 4) | pin,
+            _mode: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn block(&self) -> &crate::pac::gpioa::RegisterBlock {
+        // NOTE(unsafe) the pointer always refers to one of the GPIO register blocks
+        unsafe { &*port_ptr(self.port_id()) }
+    }
+}
+
+impl<MODE> fmt::Debug for ErasedPin<MODE> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_fmt(format_args!(
+            "P{}{}<{}>",
+            (self.port_id() + b'A') as char,
+            self.pin_id(),
+            crate::stripped_type_name::<MODE>()
+        ))
+    }
+}
+
+impl<MODE> PinExt for ErasedPin<MODE> {
+    type Mode = MODE;
+
+    #[inline(always)]
+    fn pin_id(&self) -> u8 {
+        self.pin_port & 0x0f
+    }
+    #[inline(always)]
+    fn port_id(&self) -> u8 {
+        self.pin_port >> 4
+    }
+}
+
+impl<MODE> ErasedPin<Output<MODE>> {
+    #[inline(always)]
+    pub fn set_high(&mut self) {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe { self.block().bshr.write(|w| w.bits(1 << self.pin_id())) }
+    }
+
+    #[inline(always)]
+    pub fn set_low(&mut self) {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            self.block()
+                .bshr
+                .write(|w| w.bits(1 << (self.pin_id() + 16)))
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_state(&self) -> PinState {
+        if self.is_set_low() {
+            PinState::Low
+        } else {
+            PinState::High
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_state(&mut self, state: PinState) {
+        match state {
+            PinState::Low => self.set_low(),
+            PinState::High => self.set_high(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_set_high(&self) -> bool {
+        !self.is_set_low()
+    }
+
+    #[inline(always)]
+    pub fn is_set_low(&self) -> bool {
+        self.block().outdr.read().bits() & (1 << self.pin_id()) == 0
+    }
+
+    #[inline(always)]
+    pub fn toggle(&mut self) {
+        let mask = 1 << self.pin_id();
+        let set = self.block().outdr.read().bits() & mask;
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            self.block()
+                .bshr
+                .write(|w| w.bits((set << 16) | (set ^ mask)))
+        }
+    }
+}
+
+impl ErasedPin<Output<OpenDrain>> {
+    #[inline(always)]
+    pub fn is_high(&self) -> bool {
+        !self.is_low()
+    }
+
+    #[inline(always)]
+    pub fn is_low(&self) -> bool {
+        self.block().indr.read().bits() & (1 << self.pin_id()) == 0
+    }
+}
+
+impl<MODE> ErasedPin<Input<MODE>> {
+    #[inline(always)]
+    pub fn is_high(&self) -> bool {
+        !self.is_low()
+    }
+
+    #[inline(always)]
+    pub fn is_low(&self) -> bool {
+        self.block().indr.read().bits() & (1 << self.pin_id()) == 0
+    }
+}
+
+impl<const P: char, const N: u8, MODE> TryFrom<ErasedPin<MODE>> for Pin<P, N, MODE> {
+    type Error = ErasedPin<MODE>;
+
+    /// Recover the typed pin, giving the erased pin back if its port or number don't match
+    #[inline]
+    fn try_from(pin: ErasedPin<MODE>) -> Result<Self, Self::Error> {
+        if pin.port_id() == P as u8 - b'A' && pin.pin_id() == N {
+            Ok(Self::new())
+        } else {
+            Err(pin)
+        }
+    }
+}
+
+impl<const P: char, MODE> TryFrom<ErasedPin<MODE>> for PartiallyErasedPin<P, MODE> {
+    type Error = ErasedPin<MODE>;
+
+    /// Recover the port in the type, giving the erased pin back if it is on another port
+    #[inline]
+    fn try_from(pin: ErasedPin<MODE>) -> Result<Self, Self::Error> {
+        if pin.port_id() == P as u8 - b'A' {
+            Ok(Self::new(pin.pin_id()))
+        } else {
+            Err(pin)
+        }
+    }
+}
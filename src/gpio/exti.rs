@@ -0,0 +1,116 @@
+//! External interrupts (EXTI)
+
+use super::{Input, Pin};
+use crate::afio;
+use crate::pac::EXTI;
+
+/// Edges that trigger an external interrupt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    RisingFalling,
+}
+
+/// Whether EXTI line `line` (0 to 9) is pending
+///
+/// Lines 0 to 7 are the GPIO pins, line 8 is the PVD output and line 9 the auto wake-up
+/// timer. Only reads INTFR, so it can be used from an interrupt handler without access to
+/// the pin.
+///
+/// # Panics
+///
+/// If `line` is greater than 9.
+#[inline]
+pub fn pending(line: u8) -> bool {
+    assert!(line <= 9, "EXTI line out of range");
+    // NOTE(unsafe) atomic read with no side effects
+    unsafe { (*EXTI::ptr()).intfr.read().bits() & (1 << line) != 0 }
+}
+
+/// Clear the pending flag of EXTI line `line` (0 to 9)
+///
+/// INTFR is write 1 to clear, the flags of other lines are unaffected.
+///
+/// # Panics
+///
+/// If `line` is greater than 9.
+#[inline]
+pub fn clear_pending(line: u8) {
+    assert!(line <= 9, "EXTI line out of range");
+    // NOTE(unsafe) write 1 to clear, the flags of other lines are unaffected
+    unsafe { (*EXTI::ptr()).intfr.write(|w| w.bits(1 << line)) };
+}
+
+/// External interrupt configuration of an input pin
+///
+/// Each of the 8 EXTI lines is shared by the pins with the same number on every port, only
+/// one of them can be routed to the line at a time. All lines trigger the `EXTI7_0` interrupt,
+/// use [`Self::check_interrupt`] to find out which pin caused it.
+pub trait ExtiPin {
+    /// Route this pin to its EXTI line, replacing the pin of another port using it
+    fn make_interrupt_source(&mut self, exticr: &mut afio::EXTICR);
+
+    /// Select the edges the interrupt is triggered on
+    fn trigger_on_edge(&mut self, exti: &mut EXTI, edge: Edge);
+
+    /// Unmask the interrupt of this line
+    fn enable_interrupt(&mut self, exti: &mut EXTI);
+
+    /// Mask the interrupt of this line
+    fn disable_interrupt(&mut self, exti: &mut EXTI);
+
+    /// Clear the pending flag, must be done in the interrupt handler
+    fn clear_interrupt_pending_bit(&mut self);
+
+    /// Whether this line is pending
+    fn check_interrupt(&self) -> bool;
+}
+
+impl<const P: char, const N: u8, MODE> ExtiPin for Pin<P, N, Input<MODE>> {
+    fn make_interrupt_source(&mut self, exticr: &mut afio::EXTICR) {
+        // The mux value of a line is the port number: 0b00 for A, 0b10 for C and 0b11 for D
+        exticr.set_source(N, P as u8 - b'A');
+    }
+
+    fn trigger_on_edge(&mut self, exti: &mut EXTI, edge: Edge) {
+        let (rising, falling) = match edge {
+            Edge::Rising => (true, false),
+            Edge::Falling => (false, true),
+            Edge::RisingFalling => (true, true),
+        };
+
+        exti.rtenr.modify(|r, w| unsafe {
+            w.bits(if rising {
+                r.bits() | (1 << N)
+            } else {
+                r.bits() & !(1 << N)
+            })
+        });
+        exti.ftenr.modify(|r, w| unsafe {
+            w.bits(if falling {
+                r.bits() | (1 << N)
+            } else {
+                r.bits() & !(1 << N)
+            })
+        });
+    }
+
+    fn enable_interrupt(&mut self, exti: &mut EXTI) {
+        exti.intenr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << N)) });
+    }
+
+    fn disable_interrupt(&mut self, exti: &mut EXTI) {
+        exti.intenr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << N)) });
+    }
+
+    fn clear_interrupt_pending_bit(&mut self) {
+        clear_pending(N);
+    }
+
+    fn check_interrupt(&self) -> bool {
+        pending(N)
+    }
+}
@@ -0,0 +1,195 @@
+use core::convert::Infallible;
+
+use embedded_hal_1::digital::{ErrorType, InputPin, OutputPin, PinState, StatefulOutputPin};
+
+use super::{ErasedPin, Input, OpenDrain, Output, PartiallyErasedPin, Pin};
+
+impl<const P: char, const N: u8, MODE> ErrorType for Pin<P, N, MODE> {
+    type Error = Infallible;
+}
+
+impl<const P: char, const N: u8, MODE> OutputPin for Pin<P, N, Output<MODE>> {
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+        let state = match state {
+            PinState::Low => super::PinState::Low,
+            PinState::High => super::PinState::High,
+        };
+        Pin::set_state(self, state);
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8, MODE> StatefulOutputPin for Pin<P, N, Output<MODE>> {
+    #[inline(always)]
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Pin::is_set_high(self))
+    }
+
+    #[inline(always)]
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Pin::is_set_low(self))
+    }
+
+    #[inline(always)]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        Pin::toggle(self);
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8> InputPin for Pin<P, N, Output<OpenDrain>> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self._is_low())
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self._is_low())
+    }
+}
+
+impl<const P: char, const N: u8, MODE> InputPin for Pin<P, N, Input<MODE>> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self._is_low())
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self._is_low())
+    }
+}
+
+impl<const P: char, MODE> ErrorType for PartiallyErasedPin<P, MODE> {
+    type Error = Infallible;
+}
+
+impl<const P: char, MODE> OutputPin for PartiallyErasedPin<P, Output<MODE>> {
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low();
+        Ok(())
+    }
+}
+
+impl<const P: char, MODE> StatefulOutputPin for PartiallyErasedPin<P, Output<MODE>> {
+    #[inline(always)]
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(PartiallyErasedPin::<P, Output<MODE>>::is_set_high(self))
+    }
+
+    #[inline(always)]
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(PartiallyErasedPin::<P, Output<MODE>>::is_set_low(self))
+    }
+
+    #[inline(always)]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        PartiallyErasedPin::<P, Output<MODE>>::toggle(self);
+        Ok(())
+    }
+}
+
+impl<const P: char> InputPin for PartiallyErasedPin<P, Output<OpenDrain>> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(PartiallyErasedPin::<P, Output<OpenDrain>>::is_high(self))
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(PartiallyErasedPin::<P, Output<OpenDrain>>::is_low(self))
+    }
+}
+
+impl<const P: char, MODE> InputPin for PartiallyErasedPin<P, Input<MODE>> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(PartiallyErasedPin::<P, Input<MODE>>::is_high(self))
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(PartiallyErasedPin::<P, Input<MODE>>::is_low(self))
+    }
+}
+
+impl<MODE> ErrorType for ErasedPin<MODE> {
+    type Error = Infallible;
+}
+
+impl<MODE> OutputPin for ErasedPin<Output<MODE>> {
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low();
+        Ok(())
+    }
+}
+
+impl<MODE> StatefulOutputPin for ErasedPin<Output<MODE>> {
+    #[inline(always)]
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(ErasedPin::<Output<MODE>>::is_set_high(self))
+    }
+
+    #[inline(always)]
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(ErasedPin::<Output<MODE>>::is_set_low(self))
+    }
+
+    #[inline(always)]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        ErasedPin::<Output<MODE>>::toggle(self);
+        Ok(())
+    }
+}
+
+impl InputPin for ErasedPin<Output<OpenDrain>> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(ErasedPin::<Output<OpenDrain>>::is_high(self))
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(ErasedPin::<Output<OpenDrain>>::is_low(self))
+    }
+}
+
+impl<MODE> InputPin for ErasedPin<Input<MODE>> {
+    #[inline(always)]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(ErasedPin::<Input<MODE>>::is_high(self))
+    }
+
+    #[inline(always)]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(ErasedPin::<Input<MODE>>::is_low(self))
+    }
+}
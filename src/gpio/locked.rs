@@ -0,0 +1,163 @@
+use super::*;
+
+/// Pin with a locked configuration, see [`Pin::lock`]
+///
+/// Only the output level can be changed and the input level read, the pin can't be
+/// converted to another mode or released.
+pub struct LockedPin<const P: char, const N: u8, MODE> {
+    pin: Pin<P, N, MODE>,
+}
+
+impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
+    /// Lock the configuration of this pin until the next reset
+    ///
+    /// The lock key sequence is written to LCKR, with the LCK bit of this pin as well as of
+    /// every pin already locked on this port set in all three writes:
+    ///
+    /// 1. LCKK = 1
+    /// 2. LCKK = 0
+    /// 3. LCKK = 1
+    /// 4. read LCKR, LCKK reads 0
+    /// 5. read LCKR, LCKK reads 1 once the lock is active
+    ///
+    /// Any other write in between aborts the sequence, so it runs inside a critical section.
+    pub fn lock(self) -> LockedPin<P, N, MODE> {
+        const LCKK: u32 = 1 << 8;
+
+        critical_section::with(|_| {
+            // NOTE(unsafe) LCKR is only written here, inside a critical section
+            let lckr = unsafe { &(*Gpio::<P>::ptr()).lckr };
+            let pins = (lckr.read().bits() & 0xff) | (1 << N);
+
+            unsafe {
+                lckr.write(|w| w.bits(LCKK | pins));
+                lckr.write(|w| w.bits(pins));
+                lckr.write(|w| w.bits(LCKK | pins));
+            }
+            let _ = lckr.read();
+            debug_assert!(lckr.read().lckk().bit_is_set());
+        });
+
+        LockedPin { pin: self }
+    }
+}
+
+impl<const P: char, const N: u8, MODE> fmt::Debug for LockedPin<P, N, MODE> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_fmt(format_args!("Locked({:?})", self.pin))
+    }
+}
+
+impl<const P: char, const N: u8, MODE> PinExt for LockedPin<P, N, MODE> {
+    type Mode = MODE;
+
+    #[inline(always)]
+    fn pin_id(&self) -> u8 {
+        N
+    }
+    #[inline(always)]
+    fn port_id(&self) -> u8 {
+        P as u8 - b'A'
+    }
+}
+
+impl<const P: char, const N: u8, MODE> LockedPin<P, N, Output<MODE>> {
+    #[inline(always)]
+    pub fn set_high(&mut self) {
+        self.pin.set_high()
+    }
+
+    #[inline(always)]
+    pub fn set_low(&mut self) {
+        self.pin.set_low()
+    }
+
+    #[inline(always)]
+    pub fn get_state(&self) -> PinState {
+        self.pin.get_state()
+    }
+
+    #[inline(always)]
+    pub fn set_state(&mut self, state: PinState) {
+        self.pin.set_state(state)
+    }
+
+    #[inline(always)]
+    pub fn is_set_high(&self) -> bool {
+        self.pin.is_set_high()
+    }
+
+    #[inline(always)]
+    pub fn is_set_low(&self) -> bool {
+        self.pin.is_set_low()
+    }
+
+    #[inline(always)]
+    pub fn toggle(&mut self) {
+        self.pin.toggle()
+    }
+}
+
+impl<const P: char, const N: u8> LockedPin<P, N, Output<OpenDrain>> {
+    #[inline(always)]
+    pub fn is_high(&self) -> bool {
+        self.pin.is_high()
+    }
+
+    #[inline(always)]
+    pub fn is_low(&self) -> bool {
+        self.pin.is_low()
+    }
+}
+
+impl<const P: char, const N: u8, MODE> LockedPin<P, N, Input<MODE>> {
+    #[inline(always)]
+    pub fn is_high(&self) -> bool {
+        self.pin.is_high()
+    }
+
+    #[inline(always)]
+    pub fn is_low(&self) -> bool {
+        self.pin.is_low()
+    }
+}
+
+impl<const P: char, const N: u8, MODE> embedded_hal_1::digital::ErrorType
+    for LockedPin<P, N, MODE>
+{
+    type Error = core::convert::Infallible;
+}
+
+impl<const P: char, const N: u8, MODE> embedded_hal_1::digital::OutputPin
+    for LockedPin<P, N, Output<MODE>>
+{
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low();
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8, MODE> embedded_hal_02::digital::v2::OutputPin
+    for LockedPin<P, N, Output<MODE>>
+{
+    type Error = core::convert::Infallible;
+
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low();
+        Ok(())
+    }
+}
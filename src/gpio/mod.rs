@@ -6,20 +6,52 @@ use core::marker::PhantomData;
 pub use embedded_hal_02::digital::v2::PinState;
 
 mod convert;
+mod debounce;
+mod dynamic;
+mod erased;
+pub mod exti;
 mod hal_02;
+mod hal_1;
+mod locked;
 mod partially_erased;
+pub use debounce::Debouncer;
+pub use dynamic::{Dynamic, DynamicPin, PinModeError};
+pub use erased::{EPin, ErasedPin};
+pub use exti::{Edge, ExtiPin};
+pub use locked::LockedPin;
 pub use partially_erased::{PEPin, PartiallyErasedPin};
 
 /// A filler pin type
 #[derive(Debug)]
 pub struct NoPin;
 
+/// Move the listed pins out of the `Parts` of a port, as a tuple
+///
+/// `$parts` should be a variable or a field: only the listed pins are moved out of it and
+/// the others stay available.
+///
+/// ```ignore
+/// let gpioc = p.GPIOC.split(&mut rcc);
+/// let (sda, scl) = pins!(gpioc, pc1, pc2);
+/// let led = gpioc.pc4.into_push_pull_output();
+/// ```
+#[macro_export]
+macro_rules! pins {
+    ($parts:expr, $($pin:ident),+ $(,)?) => {
+        ($($parts.$pin,)+)
+    };
+}
+
 /// Extension trait to split a GPIO peripheral in independent pins and registers
 pub trait GpioExt {
     /// The parts to split the GPIO into
     type Parts;
 
     /// Splits the GPIO block into independent pins and registers
+    ///
+    /// The port clock is enabled on APB2 and the port is reset, so all pins start as
+    /// floating inputs whatever the previous configuration was. No other RCC setup is
+    /// needed before calling this.
     fn split(self, rcc: &mut crate::rcc::Rcc) -> Self::Parts;
 }
 
@@ -63,6 +95,7 @@ pub struct PushPull;
 pub struct Analog;
 
 /// Slew rates available for Output and relevant AlternateMode Pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Speed {
     /// Slew at 10Mhz
     Mhz10 = 0b01, // (yes, this one is "less" then 2Mhz)
@@ -72,6 +105,59 @@ pub enum Speed {
     Mhz50 = 0b11,
 }
 
+impl Speed {
+    /// Order of the slew rates, the register encoding isn't sorted
+    fn rank(self) -> u8 {
+        match self {
+            Speed::Mhz2 => 0,
+            Speed::Mhz10 => 1,
+            Speed::Mhz50 => 2,
+        }
+    }
+}
+
+/// Configuration of a pin as read back from the hardware, see [`Pin::read_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinConfig {
+    Analog,
+    InputFloating,
+    InputPullUp,
+    InputPullDown,
+    Output {
+        open_drain: bool,
+        speed: Speed,
+    },
+    Alternate {
+        open_drain: bool,
+        speed: Speed,
+    },
+    /// Input mode with CNF = 0b11, which the reference manual lists as reserved
+    Reserved,
+}
+
+impl PinConfig {
+    /// Decode the 4 configuration bits of a pin (CNF in bits 2-3, MODE in bits 0-1) and its
+    /// output data bit, which selects the pull direction of pulled inputs
+    fn from_bits(cfgr: u32, odr: bool) -> Self {
+        let open_drain = cfgr & 0b0100 != 0;
+        let speed = match cfgr & 0b11 {
+            0b01 => Speed::Mhz10,
+            0b10 => Speed::Mhz2,
+            _ => Speed::Mhz50,
+        };
+
+        match (cfgr >> 2, cfgr & 0b11) {
+            (0b00, 0b00) => PinConfig::Analog,
+            (0b01, 0b00) => PinConfig::InputFloating,
+            (0b10, 0b00) if odr => PinConfig::InputPullUp,
+            (0b10, 0b00) => PinConfig::InputPullDown,
+            (0b11, 0b00) => PinConfig::Reserved,
+            (0b00 | 0b01, _) => PinConfig::Output { open_drain, speed },
+            _ => PinConfig::Alternate { open_drain, speed },
+        }
+    }
+}
+
 /// Allow setting of the slew rate of an IO pin
 ///
 /// Initially all pins are set to the maximum slew rate
@@ -79,9 +165,6 @@ pub trait OutputSpeed<CR> {
     fn set_speed(&mut self, cr: &mut CR, speed: Speed);
 }
 
-// TODO: interrupts
-// Edge, Interruptable
-
 /// Generic pin type
 ///
 /// - `MODE` is one of the pin modes (see [Modes](crate::gpio#modes) section).
@@ -136,7 +219,7 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, Output<MODE>> {
 
 // NOTE: No internal_pull_up and internal_pull_down for Output<OpenDrain>
 
-impl<const P: char, const N: u8> Pin<P, N, Alternate<PushPull>> {
+impl<const P: char, const N: u8, MODE> Pin<P, N, Alternate<MODE>> {
     /// Set pin speed
     pub fn set_speed(self, speed: Speed) -> Self {
         unsafe {
@@ -147,6 +230,27 @@ impl<const P: char, const N: u8> Pin<P, N, Alternate<PushPull>> {
 
         self
     }
+
+    /// Current slew rate of the pin
+    pub fn speed(&self) -> Speed {
+        match self.read_mode() {
+            PinConfig::Alternate { speed, .. } => speed,
+            // An alternate pin is always in one of the output modes
+            _ => Speed::Mhz50,
+        }
+    }
+
+    /// Raise the slew rate to at least `speed`, leaving faster settings alone
+    ///
+    /// Meant for drivers with fast clocks (e.g. SPI SCK and MOSI at 24 MHz), which would get
+    /// rounded edges and corrupt data through pins slowed down to 2 or 10 MHz.
+    /// [`Self::into_alternate`] already selects 50 MHz, this only matters when
+    /// [`Self::set_speed`] has been used.
+    pub fn require_speed(&mut self, speed: Speed) {
+        if self.speed().rank() < speed.rank() {
+            *self = Self::new().set_speed(speed);
+        }
+    }
 }
 
 impl<const P: char, const N: u8> Pin<P, N, Alternate<PushPull>> {
@@ -165,7 +269,39 @@ impl<const P: char, const N: u8> Pin<P, N, Alternate<PushPull>> {
     }
 }
 
-// TODO: Erase pin number, Erase pin number and port number
+impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
+    /// Read back the configuration of this pin from CFGLR, e.g. to check it while debugging
+    ///
+    /// This doesn't change anything, it should always match the `MODE` type state unless the
+    /// registers have been written behind the back of this crate.
+    pub fn read_mode(&self) -> PinConfig {
+        // NOTE(unsafe) atomic reads with no side effects
+        let (cfgr, odr) = unsafe {
+            let gpio = &*Gpio::<P>::ptr();
+            (
+                (gpio.cfglr.read().bits() >> Self::OFFSET) & 0b1111,
+                gpio.outdr.read().bits() & (1 << N) != 0,
+            )
+        };
+        PinConfig::from_bits(cfgr, odr)
+    }
+
+    /// Erase the pin number from the type
+    ///
+    /// This is useful when you want to collect the pins of a port into an array
+    #[inline]
+    pub fn erase_number(self) -> PartiallyErasedPin<P, MODE> {
+        PartiallyErasedPin::new(N)
+    }
+
+    /// Erase the pin number and the port from the type
+    ///
+    /// This is useful when you want to collect pins of different ports into an array
+    #[inline]
+    pub fn erase(self) -> ErasedPin<MODE> {
+        ErasedPin::new(P as u8 - b'A', N)
+    }
+}
 
 impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
     /// Offset into the config register
@@ -177,9 +313,13 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
     /// a short spike of an incorrect value
     #[inline(always)]
     fn _set_state(&mut self, state: PinState) {
-        match state {
-            PinState::High => self._set_high(),
-            PinState::Low => self._set_low(),
+        // Set bits are 0-15 and reset bits 16-31, select one without branching on the level
+        let offset = if state == PinState::Low { 16 } else { 0 };
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            (*Gpio::<P>::ptr())
+                .bshr
+                .write(|w| w.bits(1 << (N + offset)))
         }
     }
     #[inline(always)]
@@ -193,6 +333,16 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
         unsafe { (*Gpio::<P>::ptr()).bshr.write(|w| w.bits(1 << (16 + N))) }
     }
     #[inline(always)]
+    fn _toggle(&mut self) {
+        // Set the bit if it is currently reset and the other way around, in a single write
+        // NOTE(unsafe) atomic read with no side effects, atomic write to a stateless register
+        unsafe {
+            let gpio = &*Gpio::<P>::ptr();
+            let set = gpio.outdr.read().bits() & (1 << N);
+            gpio.bshr.write(|w| w.bits((set << 16) | (set ^ (1 << N))))
+        }
+    }
+    #[inline(always)]
     fn _is_set_low(&self) -> bool {
         // NOTE(unsafe) atomic read with no side effects
         unsafe { (*Gpio::<P>::ptr()).outdr.read().bits() & (1 << N) == 0 }
@@ -224,12 +374,10 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, Output<MODE>> {
         }
     }
 
+    /// Drive the pin to `state` with a single BSHR write
     #[inline(always)]
     pub fn set_state(&mut self, state: PinState) {
-        match state {
-            PinState::Low => self.set_low(),
-            PinState::High => self.set_high(),
-        }
+        self._set_state(state)
     }
 
     #[inline(always)]
@@ -242,20 +390,44 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, Output<MODE>> {
         self._is_set_low()
     }
 
+    /// Invert the output level
+    ///
+    /// This is one OUTDR load and one BSHR store, INDR isn't read. An XOR on OUTDR would take
+    /// the same load and store, without being atomic with respect to the other pins. For the
+    /// tightest bit-banged clocks, use [`Self::set_high`] and [`Self::set_low`] when the level
+    /// is known: they are a single store each.
+    ///
+    /// Rates derived from the release build, counting one cycle per ALU instruction, two
+    /// per taken jump and two per APB2 load or store (the flash wait state above 24 MHz
+    /// lowers the 48 MHz figures somewhat):
+    ///
+    /// - unrolled `set_high(); set_low();`: 4 cycles per period (two `sw`), about 6 MHz at
+    ///   24 MHz HCLK and 12 MHz at 48 MHz
+    /// - `loop { set_high(); set_low(); }`: 6 cycles (two `sw` and a `j`), about 4 MHz and
+    ///   8 MHz
+    /// - `loop { toggle(); }`: 10 cycles per edge (`lw` OUTDR, four ALU instructions, `sw`
+    ///   BSHR and a `j`), a 1.2 MHz square wave at 24 MHz and 2.4 MHz at 48 MHz
+    ///
+    /// Interrupts and the code between pin writes come on top of this.
     #[inline(always)]
     pub fn toggle(&mut self) {
-        self.set_state(!self.get_state())
+        self._toggle()
     }
 }
 
 // Special high/low for open drain output
 
 impl<const P: char, const N: u8> Pin<P, N, Output<OpenDrain>> {
+    /// Level on the pad, read from INDR
+    ///
+    /// Unlike [`Self::is_set_high`] this sees another device pulling the line low while the
+    /// output is released.
     #[inline(always)]
     pub fn is_high(&self) -> bool {
         !self.is_low()
     }
 
+    /// Level on the pad, read from INDR
     #[inline(always)]
     pub fn is_low(&self) -> bool {
         self._is_low()
@@ -292,6 +464,8 @@ macro_rules! gpio {
                     /// Pin
                     pub $pxi: $PXi $(<$MODE>)?,
                 )+
+                /// Whole port access
+                pub port: super::Port<$port_id>,
             }
 
             impl super::GpioExt for $GPIOX {
@@ -306,10 +480,24 @@ macro_rules! gpio {
                         $(
                             $pxi: $PXi::new(),
                         )+
+                        port: super::Port::new(),
                     }
                 }
             }
 
+            impl Parts {
+                /// Number of pins of this port
+                pub const PIN_COUNT: usize = [$(stringify!($pxi)),+].len();
+
+                /// All pins of this port as floating inputs, ordered by pin number
+                ///
+                /// Every pin is converted to a floating input first, including any left in
+                /// their reset alternate mode, then erased so they can be indexed at runtime.
+                pub fn into_array(self) -> [super::ErasedPin<Input<Floating>>; Self::PIN_COUNT] {
+                    [$(self.$pxi.into_floating_input().erase(),)+]
+                }
+            }
+
             pub type $PXn<MODE> = super::PEPin<$port_id, MODE>;
 
             $(
@@ -347,7 +535,7 @@ gpio!(GPIOC, gpioc, PC, 'C', PCn, iopcen, iopcrst, [
 
 gpio!(GPIOD, gpiod, PD, 'D', PDn, iopden, iopdrst, [
     PD0: (pd0, 0),
-    PD1: (pd1, 1, super::Alternate), // By default in SWD mode
+    PD1: (pd1, 1, super::Alternate), // By default in SWD mode, see `afio::PCFR::disable_swj`
     PD2: (pd2, 2),
     PD3: (pd3, 3),
     PD4: (pd4, 4),
@@ -356,6 +544,51 @@ gpio!(GPIOD, gpiod, PD, 'D', PDn, iopden, iopdrst, [
     PD7: (pd7, 7),
 ]);
 
+/// Access to all pins of a port at once
+///
+/// This works on the whole port regardless of which pins have been handed out, so it is up
+/// to the caller to only touch pins that are configured for it.
+pub struct Port<const P: char> {
+    _0: (),
+}
+
+impl<const P: char> Port<P> {
+    const fn new() -> Self {
+        Self { _0: () }
+    }
+
+    /// Set the pins in `set_mask` and reset the pins in `reset_mask` with a single write
+    ///
+    /// All pins change at the same time. If a pin is in both masks, it is set.
+    #[inline(always)]
+    pub fn write_pins(&mut self, set_mask: u16, reset_mask: u16) {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            (*Gpio::<P>::ptr())
+                .bshr
+                .write(|w| w.bits(((reset_mask as u32) << 16) | set_mask as u32))
+        }
+    }
+
+    /// Read the input levels of all pins with a single read
+    ///
+    /// Bit `n` is set if pin `n` is high. Only the low 8 bits are used on the CH32V003.
+    #[inline(always)]
+    pub fn read_port(&self) -> u16 {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*Gpio::<P>::ptr()).indr.read().bits() as u16 }
+    }
+}
+
+/// Register block of the port with the given id, as returned by [`PinExt::port_id`]
+pub(crate) fn port_ptr(port_id: u8) -> *const crate::pac::gpioa::RegisterBlock {
+    match port_id {
+        2 => Gpio::<'C'>::ptr(),
+        3 => Gpio::<'D'>::ptr(),
+        _ => Gpio::<'A'>::ptr(),
+    }
+}
+
 struct Gpio<const P: char>;
 impl<const P: char> Gpio<P> {
     const fn ptr() -> *const crate::pac::gpioa::RegisterBlock {
@@ -367,6 +600,31 @@ impl<const P: char> Gpio<P> {
         }
     }
 }
+macro_rules! adc_pins {
+    ($($pin:ty => $chan:expr),+ $(,)?) => {
+        $(
+            impl embedded_hal_02::adc::Channel<crate::pac::ADC1> for $pin {
+                type ID = u8;
+
+                fn channel() -> u8 {
+                    $chan
+                }
+            }
+        )+
+    };
+}
+
+adc_pins!(
+    gpioa::PA2<Analog> => 0,
+    gpioa::PA1<Analog> => 1,
+    gpioc::PC4<Analog> => 2,
+    gpiod::PD2<Analog> => 3,
+    gpiod::PD3<Analog> => 4,
+    gpiod::PD5<Analog> => 5,
+    gpiod::PD6<Analog> => 6,
+    gpiod::PD4<Analog> => 7,
+);
+
 use crate::serial;
 impl serial::Ck<0> for gpiod::PD4<Alternate<PushPull>> {}
 impl serial::Tx<0> for gpiod::PD5<Alternate<PushPull>> {}
@@ -391,3 +649,8 @@ impl serial::Tx<3> for gpioc::PC0<Alternate<PushPull>> {}
 impl serial::Rx<3> for gpioc::PC1<Input<Floating>> {}
 impl serial::Cts<3> for gpioc::PC6<Input<Floating>> {}
 impl serial::Rts<3> for gpioc::PC7<Alternate<PushPull>> {}
+
+impl serial::HalfDuplex<0> for gpiod::PD5<Alternate<OpenDrain>> {}
+impl serial::HalfDuplex<1> for gpiod::PD0<Alternate<OpenDrain>> {}
+impl serial::HalfDuplex<2> for gpiod::PD6<Alternate<OpenDrain>> {}
+impl serial::HalfDuplex<3> for gpioc::PC0<Alternate<OpenDrain>> {}
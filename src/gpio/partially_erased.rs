@@ -12,12 +12,18 @@ pub struct PartiallyErasedPin<const P: char, MODE> {
 }
 
 impl<const P: char, MODE> PartiallyErasedPin<P, MODE> {
-    // pub(crate) fn new(i: u8) -> Self {
-    //     Self {
-    //         i,
-    //         _mode: PhantomData,
-    //     }
-    // }
+    pub(crate) fn new(i: u8) -> Self {
+        Self {
+            i,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Erase the port as well, see [`ErasedPin`]
+    #[inline]
+    pub fn erase(self) -> ErasedPin<MODE> {
+        ErasedPin::new(P as u8 - b'A', self.i)
+    }
 }
 
 impl<const P: char, MODE> fmt::Debug for PartiallyErasedPin<P, MODE> {
@@ -91,10 +97,12 @@ impl<const P: char, MODE> PartiallyErasedPin<P, Output<MODE>> {
 
     #[inline(always)]
     pub fn toggle(&mut self) {
-        if self.is_set_low() {
-            self.set_high()
-        } else {
-            self.set_low()
+        // NOTE(unsafe) atomic read with no side effects, atomic write to a stateless register
+        unsafe {
+            let gpio = &*Gpio::<P>::ptr();
+            let set = gpio.outdr.read().bits() & (1 << self.i);
+            gpio.bshr
+                .write(|w| w.bits((set << 16) | (set ^ (1 << self.i))))
         }
     }
 }
@@ -108,7 +116,7 @@ impl<const P: char> PartiallyErasedPin<P, Output<OpenDrain>> {
     #[inline(always)]
     pub fn is_low(&self) -> bool {
         // NOTE(unsafe) atomic read with no side effects
-        unsafe { (*Gpio::<P>::ptr()).outdr.read().bits() & (1 << self.i) == 0 }
+        unsafe { (*Gpio::<P>::ptr()).indr.read().bits() & (1 << self.i) == 0 }
     }
 }
 
@@ -124,3 +132,17 @@ impl<const P: char, MODE> PartiallyErasedPin<P, Input<MODE>> {
         unsafe { (*Gpio::<P>::ptr()).indr.read().bits() & (1 << self.i) == 0 }
     }
 }
+
+impl<const P: char, const N: u8, MODE> TryFrom<PartiallyErasedPin<P, MODE>> for Pin<P, N, MODE> {
+    type Error = PartiallyErasedPin<P, MODE>;
+
+    /// Recover the typed pin, giving the partially erased pin back if its number doesn't match
+    #[inline]
+    fn try_from(pin: PartiallyErasedPin<P, MODE>) -> Result<Self, Self::Error> {
+        if pin.i == N {
+            Ok(Self::new())
+        } else {
+            Err(pin)
+        }
+    }
+}
@@ -1,19 +1,34 @@
 use fugit::{HertzU32, RateExtU32};
 
 use crate::{
+    afio::PCFR,
+    delay::CycleDelay,
+    dma::{self, Transfer, TransferPayload},
     gpio::*,
     pac::{
         i2c1::{star1, star2},
-        AFIO, I2C1,
+        I2C1,
     },
     rcc::{BusClock, Clocks, Enable, Rcc, Reset},
 };
 
+/// I2C slave address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Address {
+    /// 7-bit address, without the direction flag
+    SevenBit(u8),
+    /// 10-bit address
+    TenBit(u16),
+}
+
 /// Ready to use I2C peripheral
 pub struct I2c<Scl, Sda> {
     i2c: I2C1,
     scl: Scl,
     sda: Sda,
+    timeout: Option<u32>,
+    arbitration_retries: u8,
+    delay: CycleDelay,
 }
 
 /// I2C low/high duty cycle when using Fast Mode (> 100kHz)
@@ -25,11 +40,57 @@ pub enum DutyCycle {
     Perc36,
 }
 
+/// Decoded STAR1 and STAR2 flags, see [`I2c::status`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I2cStatus {
+    /// A transfer is in progress on the bus (BUSY)
+    pub busy: bool,
+    /// The peripheral is master of the bus (MSL)
+    pub master: bool,
+    /// Bytes are being transmitted rather than received (TRA)
+    pub transmitter: bool,
+    /// A START has been sent (SB)
+    pub start_sent: bool,
+    /// The address has been sent as a master or matched as a slave (ADDR)
+    pub address_matched: bool,
+    /// The last byte transfer has finished (BTF)
+    pub byte_transferred: bool,
+    /// A received byte is waiting in DATAR (RxNE)
+    pub rx_not_empty: bool,
+    /// DATAR can take the next byte (TxE)
+    pub tx_empty: bool,
+    /// A STOP was detected as a slave (STOPF)
+    pub stop_detected: bool,
+    /// No acknowledgement was received (AF)
+    pub acknowledge_failure: bool,
+    /// Arbitration was lost to another master (ARLO)
+    pub arbitration_lost: bool,
+    /// A misplaced START or STOP was detected (BERR)
+    pub bus_error: bool,
+    /// A byte was lost as a slave because of clock stretching being disabled (OVR)
+    pub overrun: bool,
+}
+
 /// I2C peripheral configuration
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct I2cConfig {
     pub speed: HertzU32,
     pub duty: DutyCycle,
+    /// Number of status polls to wait for the bus before giving up with [`Error::Timeout`].
+    /// `None` waits forever.
+    ///
+    /// This also bounds how long a slave may stretch SCL: the master can't disable clock
+    /// stretching, it has to wait for SCL to be released before it can continue. A short
+    /// timeout fails fast on a hung slave, but can abort transfers with slow slaves that
+    /// legitimately stretch the clock, e.g. EEPROMs during a page write.
+    pub timeout: Option<u32>,
+    /// Run [`I2c::recover_bus`] when initialising the peripheral
+    pub recover_on_init: bool,
+    /// Let the peripheral calculate the SMBus PEC of transmitted bytes (ENPEC),
+    /// see [`I2c::write_pec`]
+    pub enable_pec: bool,
+    /// Number of times a transaction is retried after losing arbitration to another master
+    pub arbitration_retries: u8,
 }
 
 impl I2cConfig {
@@ -39,6 +100,10 @@ impl I2cConfig {
         Self {
             speed: HertzU32::kHz(100),
             duty: DutyCycle::Perc33,
+            timeout: None,
+            recover_on_init: false,
+            enable_pec: false,
+            arbitration_retries: 0,
         }
     }
 
@@ -48,6 +113,10 @@ impl I2cConfig {
         Self {
             speed: HertzU32::kHz(400),
             duty: DutyCycle::Perc33,
+            timeout: None,
+            recover_on_init: false,
+            enable_pec: false,
+            arbitration_retries: 0,
         }
     }
 
@@ -57,10 +126,23 @@ impl I2cConfig {
         Self {
             speed: HertzU32::kHz(1000),
             duty: DutyCycle::Perc33,
+            timeout: None,
+            recover_on_init: false,
+            enable_pec: false,
+            arbitration_retries: 0,
         }
     }
 }
 
+impl I2cConfig {
+    /// Give up waiting for the bus after polling its status `cycles` times
+    #[inline]
+    pub const fn with_timeout(mut self, cycles: u32) -> I2cConfig {
+        self.timeout = Some(cycles);
+        self
+    }
+}
+
 /// 400kHz/33%
 impl Default for I2cConfig {
     fn default() -> Self {
@@ -68,66 +150,195 @@ impl Default for I2cConfig {
     }
 }
 
+/// Reasons an [`I2cConfig`] can't be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum I2cConfigError {
+    /// The peripheral clock is below the 2MHz minimum
+    PeripheralClockTooLow,
+    /// The speed is zero or above 1MHz (Fast Mode Plus)
+    InvalidSpeed,
+    /// [`DutyCycle::Perc36`] was requested in Standard Mode (<= 100kHz), which only has a 50% duty cycle
+    InvalidDutyCycle,
+    /// The speed can't be generated from the peripheral clock
+    UnachievableSpeed,
+}
+
+/// Register values for a validated [`I2cConfig`]
+#[derive(Debug, Clone, Copy)]
+struct Timing {
+    freq: u8,
+    ccr: u16,
+    fast_mode: bool,
+    duty: bool,
+}
+
+impl Timing {
+    fn new(config: &I2cConfig, clocks: &Clocks) -> Result<Self, I2cConfigError> {
+        let pclk = I2C1::clock(clocks).to_Hz();
+
+        // Valid range 2-36mhz, above that the field is saturated
+        let freq = pclk / 1_000_000;
+        if freq < 2 {
+            return Err(I2cConfigError::PeripheralClockTooLow);
+        }
+        let freq = freq.min(36);
+
+        let speed = config.speed.to_Hz();
+        if speed == 0 || speed > 1_000_000 {
+            return Err(I2cConfigError::InvalidSpeed);
+        }
+
+        let fast_mode = config.speed > 100u32.kHz::<1, 1>();
+        if !fast_mode && config.duty == DutyCycle::Perc36 {
+            return Err(I2cConfigError::InvalidDutyCycle);
+        }
+
+        // Calculate bus speed, SCL high and low times are multiples of the peripheral clock:
+        // - Standard mode: T_high = T_low = CCR * T_pclk, so CCR = f_pclk / (2 * f_scl)
+        // - Fast mode, DUTY = 0: T_high = CCR * T_pclk, T_low = 2 * T_high,
+        //   so CCR = f_pclk / (3 * f_scl)
+        // - Fast mode, DUTY = 1: T_high = 9 * CCR * T_pclk, T_low = 16 * CCR * T_pclk,
+        //   so CCR = f_pclk / (25 * f_scl)
+        //
+        // Unlike the STM32 I2C this peripheral is derived from, there is no TRISE register
+        // to compensate for slow rising edges. The bus speed is only reached if the pull-ups
+        // are strong enough for the bus capacitance, expect a lower speed otherwise.
+        let ccr = match (fast_mode, config.duty) {
+            (false, _) => pclk / (speed * 2),
+            (true, DutyCycle::Perc33) => pclk / (speed * 3),
+            (true, DutyCycle::Perc36) => pclk / (speed * 25),
+        };
+
+        // Standard mode needs a CCR of at least 4, fast mode of at least 1. CCR is 12 bits wide.
+        let min = if fast_mode { 1 } else { 4 };
+        if !(min..=0xfff).contains(&ccr) {
+            return Err(I2cConfigError::UnachievableSpeed);
+        }
+
+        Ok(Self {
+            freq: freq as u8,
+            ccr: ccr as u16,
+            fast_mode,
+            duty: config.duty == DutyCycle::Perc36,
+        })
+    }
+
+    /// Write the timing registers, the peripheral must be disabled
+    fn apply(&self, i2c: &I2C1) {
+        // Configure peripheral clock
+        i2c.ctlr2.modify(|_, w| w.freq().variant(self.freq));
+
+        // Set clock flags
+        i2c.ckcfgr.modify(|_, w| {
+            w.ccr() // Clock rate
+                .variant(self.ccr)
+                .f_s() // Fast mode
+                .bit(self.fast_mode)
+                .duty() // Duty cycle
+                .bit(self.duty)
+        });
+    }
+}
+
 impl<Scl, Sda> I2c<Scl, Sda>
 where
     (Scl, Sda): I2C1Pair,
 {
     /// Initialise the I2C1 peripheral with valid SCL and SDA pins
+    ///
+    /// # Panics
+    ///
+    /// If `config` can't be used with the current clocks, see [`Self::try_i2c1`]
     pub fn i2c1(
         i2c: I2C1,
         scl: Scl,
         sda: Sda,
         config: I2cConfig,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Self
+    where
+        Scl: PinExt,
+        Sda: PinExt,
+    {
+        Self::try_i2c1(i2c, scl, sda, config, pcfr, rcc, clocks).expect("invalid I2C configuration")
+    }
+
+    /// Initialise the I2C1 peripheral with valid SCL and SDA pins, checking the configuration
+    ///
+    /// Fails without touching the peripheral if the requested speed can't be reached with
+    /// the peripheral clock, see [`I2cConfigError`].
+    pub fn try_i2c1(
+        i2c: I2C1,
+        scl: Scl,
+        sda: Sda,
+        config: I2cConfig,
+        pcfr: &mut PCFR,
         rcc: &mut Rcc,
         clocks: &Clocks,
-    ) -> Self {
+    ) -> Result<Self, I2cConfigError>
+    where
+        Scl: PinExt,
+        Sda: PinExt,
+    {
+        let timing = Timing::new(&config, clocks)?;
+
         // Ensure i2c is enabled and reset to known state
         I2C1::enable(&mut rcc.apb1);
         I2C1::reset(&mut rcc.apb1);
 
-        AFIO::enable(&mut rcc.apb2);
-
         // Reset peripheral state, just to be safe?
         i2c.ctlr1.modify(|_, w| w.swrst().set_bit());
         i2c.ctlr1.modify(|_, w| w.swrst().clear_bit());
 
         // Configure the remap bits in AFIO to match our pin selection
         let (high, low) = <(Scl, Sda) as I2C1Pair>::REMAP_BITS;
-        unsafe {
-            (*AFIO::ptr())
-                .pcfr
-                .modify(|_, w| w.i2c1remap1().bit(high).i2c1rm().bit(low));
-        }
+        pcfr.set_i2c1_remap(((high as u8) << 1) | low as u8);
 
-        // Configure peripheral clock (valid range 2-36mhz)
-        let freq = I2C1::clock(clocks).to_MHz().clamp(2, 36);
-        i2c.ctlr2.modify(|_, w| w.freq().variant(freq as u8));
+        timing.apply(&i2c);
 
-        let fast_mode = config.speed > 100u32.kHz::<1, 1>();
-        let speed = config.speed.to_Hz();
+        // Start peripheral and enable acknowledgements
+        i2c.ctlr1.modify(|_, w| w.pe().set_bit());
+        i2c.ctlr1.modify(|_, w| w.ack().set_bit());
+        i2c.ctlr1.modify(|_, w| w.enpec().bit(config.enable_pec));
 
-        // Calculate bus speed. The source of these values is a bit obscure?
-        let ccr = match (fast_mode, config.duty) {
-            (false, _) => I2C1::clock(clocks).to_Hz() / (speed * 2),
-            (true, DutyCycle::Perc33) => I2C1::clock(clocks).to_Hz() / (speed * 3),
-            (true, DutyCycle::Perc36) => I2C1::clock(clocks).to_Hz() / (speed * 25),
+        let mut i2c = Self {
+            i2c,
+            scl,
+            sda,
+            timeout: config.timeout,
+            arbitration_retries: config.arbitration_retries,
+            delay: CycleDelay::new(clocks),
         };
 
-        // Set clock flags
-        i2c.ckcfgr.modify(|_, w| {
-            w.ccr() // Clock rate
-                .variant(ccr as u16)
-                .f_s() // Fast mode
-                .bit(fast_mode)
-                .duty() // Duty cycle
-                .bit(config.duty == DutyCycle::Perc36)
-        });
+        if config.recover_on_init {
+            i2c.recover_bus();
+        }
 
-        // Start peripheral and enable acknowledgements
-        i2c.ctlr1.modify(|_, w| w.pe().set_bit());
-        i2c.ctlr1.modify(|_, w| w.ack().set_bit());
+        Ok(i2c)
+    }
+
+    /// Change the bus speed, duty cycle, timeout and PEC setting without releasing the pins
+    ///
+    /// The peripheral is briefly disabled while the timing registers are written, so this
+    /// must not be called in the middle of a transfer. Fails without changing anything if
+    /// `config` is invalid, as with [`Self::try_i2c1`].
+    pub fn set_speed(&mut self, config: I2cConfig, clocks: &Clocks) -> Result<(), I2cConfigError> {
+        let timing = Timing::new(&config, clocks)?;
 
-        Self { i2c, scl, sda }
+        self.i2c.ctlr1.modify(|_, w| w.pe().clear_bit());
+        timing.apply(&self.i2c);
+        self.i2c.ctlr1.modify(|_, w| w.pe().set_bit());
+        self.i2c
+            .ctlr1
+            .modify(|_, w| w.ack().set_bit().enpec().bit(config.enable_pec));
+
+        self.timeout = config.timeout;
+        self.arbitration_retries = config.arbitration_retries;
+        self.delay = CycleDelay::new(clocks);
+
+        Ok(())
     }
 
     /// Deconstruct the I2C peripheral and return it's raw hardware resources
@@ -138,14 +349,302 @@ where
         (self.i2c, self.scl, self.sda)
     }
 
+    /// Free the bus from a slave holding SDA low, e.g. because the MCU was reset mid-transfer
+    ///
+    /// The peripheral is disabled and SCL is clocked at ~100kHz up to 9 times until the slave
+    /// releases SDA, after which a STOP condition is generated manually and the peripheral is
+    /// enabled again. Both lines are bit-banged as open-drain outputs, so a slave stretching
+    /// the clock is never driven against, and are put back into their alternate open-drain
+    /// mode afterwards.
+    pub fn recover_bus(&mut self)
+    where
+        Scl: PinExt,
+        Sda: PinExt,
+    {
+        use embedded_hal_1::delay::DelayNs;
+
+        // Open drain output, 50MHz
+        const OPEN_DRAIN_OUTPUT: u32 = 0b0111;
+
+        type RawPin = (*const crate::pac::gpioa::RegisterBlock, u8);
+
+        let scl = (port_ptr(self.scl.port_id()), self.scl.pin_id());
+        let sda = (port_ptr(self.sda.port_id()), self.sda.pin_id());
+
+        // NOTE(unsafe) we own both pins, so only their bits are modified
+        let set_config = |(port, n): RawPin, cfg: u32| unsafe {
+            let mut old = 0;
+            (*port).cfglr.modify(|r, w| {
+                old = (r.bits() >> (n * 4)) & 0b1111;
+                w.bits((r.bits() & !(0b1111 << (n * 4))) | (cfg << (n * 4)))
+            });
+            old
+        };
+        let set = |(port, n): RawPin, high: bool| unsafe {
+            (*port)
+                .bshr
+                .write(|w| w.bits(if high { 1 << n } else { 1 << (n + 16) }))
+        };
+        let is_high = |(port, n): RawPin| unsafe { (*port).indr.read().bits() & (1 << n) != 0 };
+
+        self.i2c.ctlr1.modify(|_, w| w.pe().clear_bit());
+
+        // Release both lines before handing them over to the GPIO
+        set(scl, true);
+        set(sda, true);
+        let scl_config = set_config(scl, OPEN_DRAIN_OUTPUT);
+        let sda_config = set_config(sda, OPEN_DRAIN_OUTPUT);
+        self.delay.delay_us(5);
+
+        // Clock out whatever the slave is still trying to send
+        for _ in 0..9 {
+            if is_high(sda) {
+                break;
+            }
+            set(scl, false);
+            self.delay.delay_us(5);
+            set(scl, true);
+            self.delay.delay_us(5);
+        }
+
+        // STOP: SDA going high while SCL is high
+        set(scl, false);
+        self.delay.delay_us(5);
+        set(sda, false);
+        self.delay.delay_us(5);
+        set(scl, true);
+        self.delay.delay_us(5);
+        set(sda, true);
+        self.delay.delay_us(5);
+
+        set_config(scl, scl_config);
+        set_config(sda, sda_config);
+
+        self.i2c.ctlr1.modify(|_, w| w.pe().set_bit());
+    }
+
+    /// Write `bytes` and read into `buffer` as two separate transactions
+    ///
+    /// Unlike `write_read`, which turns the bus around with a repeated START, this releases
+    /// the bus with a STOP after the write. Only use this with devices that can't handle
+    /// repeated STARTs.
+    pub fn write_then_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        embedded_hal_1::i2c::I2c::write(self, address, bytes)?;
+        embedded_hal_1::i2c::I2c::read(self, address, buffer)
+    }
+
+    /// Probe every non-reserved 7-bit address (0x08..=0x77), calling `f` with each one that ACKs
+    ///
+    /// Each probe is a START, the address with the write flag and a STOP. The ACK failure flag
+    /// set by a missing device is cleared before moving on to the next address.
+    pub fn scan(&mut self, mut f: impl FnMut(u8)) -> Result<(), Error> {
+        for address in 0x08..=0x77 {
+            self.wait_while(|_, s2| s2.busy().bit_is_set())?;
+
+            let probe = self
+                .send_start(false)
+                .and_then(|_| self.send_address(Address::SevenBit(address), false));
+            match probe {
+                Ok(()) => {
+                    self.clear_addr();
+                    self.send_stop();
+                    f(address);
+                }
+                Err(Error::AcknowledgeFailure) => self.send_stop(),
+                Err(e) => {
+                    self.send_stop();
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write `bytes` to the device at the 10-bit `address`
+    pub fn write_10bit(&mut self, address: u16, bytes: &[u8]) -> Result<(), Error> {
+        use embedded_hal_1::i2c::Operation;
+
+        self.transaction_at(Address::TenBit(address), &mut [Operation::Write(bytes)])
+    }
+
+    /// Fill `buffer` with bytes read from the device at the 10-bit `address`
+    pub fn read_10bit(&mut self, address: u16, buffer: &mut [u8]) -> Result<(), Error> {
+        use embedded_hal_1::i2c::Operation;
+
+        self.transaction_at(Address::TenBit(address), &mut [Operation::Read(buffer)])
+    }
+
+    /// Write `bytes` followed by the SMBus PEC to the device at `address`
+    ///
+    /// The PEC covers the address byte and all data bytes. With [`I2cConfig::enable_pec`]
+    /// it is calculated and appended by the peripheral, otherwise in software.
+    pub fn write_pec(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        let result = self.write_pec_inner(address, bytes);
+        self.end_transaction(result)
+    }
+
+    /// Fill `buffer` with bytes read from the device at `address`, followed by the SMBus PEC
+    ///
+    /// The received PEC is checked in software, [`Error::PecError`] is returned if it doesn't
+    /// match. The contents of `buffer` are left in place either way.
+    pub fn read_pec(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        let result = self.read_pec_inner(address, buffer);
+        self.end_transaction(result)
+    }
+
+    /// Write `bytes` to the device at `address`, returning how many were acknowledged
+    ///
+    /// On failure the error is returned along with the number of bytes that made it to the
+    /// device, so the transfer can be resumed or retried. To get an exact count each byte is
+    /// only sent once the previous one has been acknowledged, which is a bit slower than
+    /// a regular write.
+    pub fn write_counted(&mut self, address: u8, bytes: &[u8]) -> Result<usize, (Error, usize)> {
+        let mut count = 0;
+        let result = self.write_counted_inner(address, bytes, &mut count);
+        self.end_transaction(result)
+            .map(|_| count)
+            .map_err(|e| (e, count))
+    }
+
+    /// Fill `buffer` with bytes read from the device at `address`, returning how many were read
+    ///
+    /// On failure the error is returned along with the number of bytes that were received,
+    /// these are at the start of `buffer`.
+    pub fn read_counted(
+        &mut self,
+        address: u8,
+        buffer: &mut [u8],
+    ) -> Result<usize, (Error, usize)> {
+        let mut count = 0;
+        let result = self.read_counted_inner(address, buffer, &mut count);
+        self.end_transaction(result)
+            .map(|_| count)
+            .map_err(|e| (e, count))
+    }
+
+    /// Read the current status flags
+    ///
+    /// STAR1 is read before STAR2 as required. Note that this sequence also clears the ADDR
+    /// flag, so this shouldn't be used while an address phase is being handled elsewhere.
+    pub fn status(&self) -> I2cStatus {
+        let s1 = self.i2c.star1.read();
+        let s2 = self.i2c.star2.read();
+
+        I2cStatus {
+            busy: s2.busy().bit_is_set(),
+            master: s2.msl().bit_is_set(),
+            transmitter: s2.tra().bit_is_set(),
+            start_sent: s1.sb().bit_is_set(),
+            address_matched: s1.addr().bit_is_set(),
+            byte_transferred: s1.btf().bit_is_set(),
+            rx_not_empty: s1.rx_ne().bit_is_set(),
+            tx_empty: s1.tx_e().bit_is_set(),
+            stop_detected: s1.stopf().bit_is_set(),
+            acknowledge_failure: s1.af().bit_is_set(),
+            arbitration_lost: s1.arlo().bit_is_set(),
+            bus_error: s1.berr().bit_is_set(),
+            overrun: s1.ovr().bit_is_set(),
+        }
+    }
+
+    /// Bring the peripheral back into a usable state after a transaction failed
+    ///
+    /// The error flags in STAR1 (BERR, ARLO, AF and OVR) are cleared by writing 0 to them.
+    /// If the peripheral is still master of the bus, e.g. after a NACK, a STOP is sent.
+    /// BUSY can't be cleared by software, if it is still set afterwards the peripheral lost
+    /// track of the bus state and is put through a software reset (SWRST). The reset clears
+    /// every register, the configuration is restored afterwards.
+    ///
+    /// This doesn't help if a slave is holding SDA low, use [`Self::recover_bus`] for that.
+    pub fn clear_errors(&mut self) {
+        self.clear_error_flags();
+        self.i2c
+            .ctlr1
+            .modify(|_, w| w.pos().clear_bit().ack().set_bit());
+
+        if self.i2c.star2.read().msl().bit_is_set() {
+            self.send_stop();
+            let _ = self.wait_while(|_, s2| s2.msl().bit_is_set());
+        }
+
+        if self.i2c.star2.read().busy().bit_is_set() {
+            self.software_reset();
+        }
+    }
+
+    /// Clear the STAR1 error flags, these are cleared by writing 0
+    fn clear_error_flags(&mut self) {
+        self.i2c.star1.modify(|_, w| {
+            w.berr()
+                .clear_bit()
+                .af()
+                .clear_bit()
+                .arlo()
+                .clear_bit()
+                .ovr()
+                .clear_bit()
+        });
+    }
+
+    /// Toggle SWRST, keeping the timing and address configuration
+    fn software_reset(&mut self) {
+        let ctlr2 = self.i2c.ctlr2.read().bits();
+        let oaddr1 = self.i2c.oaddr1.read().bits();
+        let oaddr2 = self.i2c.oaddr2.read().bits();
+        let ckcfgr = self.i2c.ckcfgr.read().bits();
+
+        self.i2c.ctlr1.modify(|_, w| w.swrst().set_bit());
+        self.i2c.ctlr1.modify(|_, w| w.swrst().clear_bit());
+
+        // NOTE(unsafe) the values were read back from the same registers
+        unsafe {
+            self.i2c.ctlr2.write(|w| w.bits(ctlr2));
+            self.i2c.oaddr1.write(|w| w.bits(oaddr1));
+            self.i2c.oaddr2.write(|w| w.bits(oaddr2));
+            self.i2c.ckcfgr.write(|w| w.bits(ckcfgr));
+        }
+
+        self.i2c.ctlr1.modify(|_, w| w.pe().set_bit());
+        self.i2c.ctlr1.modify(|_, w| w.ack().set_bit());
+    }
+
+    /// Spin while `f` returns true, giving up once the configured timeout runs out
+    #[inline]
+    fn spin_while(&self, mut f: impl FnMut() -> bool) -> Result<(), Error> {
+        let mut remaining = self.timeout;
+        while f() {
+            if let Some(remaining) = remaining.as_mut() {
+                if *remaining == 0 {
+                    return Err(Error::Timeout);
+                }
+                *remaining -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Spin while `f` returns true, bailing out early if another master took over the bus
     #[inline]
-    fn wait_while(&self, f: impl Fn(star1::R, star2::R) -> bool) {
-        while {
+    fn wait_while(&self, f: impl Fn(star1::R, star2::R) -> bool) -> Result<(), Error> {
+        let mut arbitration_lost = false;
+        self.spin_while(|| {
             // // It is important to read STAR1 before STAR2
             let s1 = self.i2c.star1.read();
             let s2 = self.i2c.star2.read();
-            f(s1, s2)
-        } {}
+            arbitration_lost = s1.arlo().bit_is_set();
+            !arbitration_lost && f(s1, s2)
+        })?;
+
+        if arbitration_lost {
+            return Err(Error::ArbitrationLost);
+        }
+        Ok(())
     }
 
     /// Check STAR1 error flags
@@ -164,50 +663,105 @@ where
             Ok(())
         }
     }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Error {
-    BusError,
-    AcknowledgeFailure,
-    ArbitrationLost,
-    Overrun,
-}
-
-impl<Scl, Sda> embedded_hal_02::blocking::i2c::Write for I2c<Scl, Sda>
-where
-    (Scl, Sda): I2C1Pair,
-{
-    type Error = Error;
-
-    #[inline(never)]
-    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
-        // Wait till idle
-        self.wait_while(|_, s2| s2.busy().bit_is_set());
 
-        // Send start event and take control of the bus
-        self.i2c.ctlr1.modify(|_, w| w.start().set_bit());
+    /// Send a START (or repeated START) event and wait until master mode is assigned
+    ///
+    /// If the START has already been requested, e.g. at the end of a read, only the wait is performed.
+    fn send_start(&mut self, requested: bool) -> Result<(), Error> {
+        if !requested {
+            self.i2c.ctlr1.modify(|_, w| w.start().set_bit());
+        }
 
         // Wait till start has been sent and master mode is assigned
         self.wait_while(|s1, s2| {
             s1.sb().bit_is_clear() || s2.busy().bit_is_clear() || s2.msl().bit_is_clear()
-        });
+        })
+    }
 
-        // Send address + write flag
-        self.i2c.datar.write(|w| w.datar().variant(address << 1));
+    /// Send a STOP event, releasing the bus
+    fn send_stop(&mut self) {
+        self.i2c.ctlr1.modify(|_, w| w.stop().set_bit());
+    }
 
-        // Wait address is till sent
-        self.wait_while(|s1, s2| {
-            s1.addr().bit_is_clear()
-                || s1.tx_e().bit_is_clear()
-                || s2.busy().bit_is_clear()
-                || s2.msl().bit_is_clear()
-                || s2.tra().bit_is_clear()
-        });
+    /// Request the event that terminates a receive
+    fn send_ending(&mut self, ending: Ending) {
+        match ending {
+            Ending::Stop => self.send_stop(),
+            Ending::Restart => self.i2c.ctlr1.modify(|_, w| w.start().set_bit()),
+        }
+    }
+
+    /// Send the address with the direction flag and wait for it to be acknowledged
+    ///
+    /// For 10-bit reads this also performs the write header and the repeated START that
+    /// are required before the read header. The ADDR flag is left set, see [`Self::clear_addr`].
+    fn send_address(&mut self, address: Address, read: bool) -> Result<(), Error> {
+        match address {
+            Address::SevenBit(address) => {
+                self.i2c
+                    .datar
+                    .write(|w| w.datar().variant((address << 1) | read as u8));
+            }
+            Address::TenBit(address) => {
+                // 0b11110 followed by the 2 high address bits and the write flag
+                let header = 0b1111_0000 | ((address >> 7) as u8 & 0b110);
+                self.i2c.datar.write(|w| w.datar().variant(header));
+                self.wait_address_flag(|s1| s1.add10().bit_is_set())?;
+
+                // Writing the low address bits clears ADD10
+                self.i2c.datar.write(|w| w.datar().variant(address as u8));
+
+                if read {
+                    self.wait_address_flag(|s1| s1.addr().bit_is_set())?;
+                    self.clear_addr();
+
+                    // Turn around and send the header again with the read flag
+                    self.send_start(false)?;
+                    self.i2c.datar.write(|w| w.datar().variant(header | 1));
+                }
+            }
+        }
+
+        self.wait_address_flag(|s1| s1.addr().bit_is_set())
+    }
+
+    /// Wait for a flag to be set during the address phase, bail out early if nobody responded
+    fn wait_address_flag(&mut self, f: impl Fn(&star1::R) -> bool) -> Result<(), Error> {
+        // Only STAR1 may be read here, reading STAR2 would clear ADDR.
+        self.spin_while(|| {
+            let s1 = self.i2c.star1.read();
+            !f(&s1) && s1.af().bit_is_clear() && s1.arlo().bit_is_clear()
+        })?;
+
+        if self.i2c.star1.read().arlo().bit_is_set() {
+            return Err(Error::ArbitrationLost);
+        }
+
+        if self.i2c.star1.read().af().bit_is_set() {
+            self.i2c.star1.modify(|_, w| w.af().clear_bit());
+            return Err(Error::AcknowledgeFailure);
+        }
+
+        Ok(())
+    }
+
+    /// Clear the ADDR flag by reading STAR1 followed by STAR2
+    fn clear_addr(&mut self) {
+        let _ = self.i2c.star1.read();
+        let _ = self.i2c.star2.read();
+    }
+
+    /// Send each byte one by one and wait for the whole transmission to complete
+    ///
+    /// Nothing is sent for an empty slice, BTF would never be set and there is nothing to wait
+    /// for. A zero-length write thus only checks that the address was acknowledged.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
 
-        // Send each byte one by one
         for byte in bytes {
-            self.wait_while(|a, _| a.tx_e().bit_is_clear());
+            self.wait_while(|a, _| a.tx_e().bit_is_clear())?;
             self.i2c.datar.write(|w| w.datar().variant(*byte));
         }
 
@@ -218,98 +772,1193 @@ where
                 || s2.busy().bit_is_clear()
                 || s2.msl().bit_is_clear()
                 || s2.tra().bit_is_clear()
-        });
+        })
+    }
 
-        // Stop transmission
-        self.i2c.ctlr1.modify(|_, w| w.stop().set_bit());
+    /// Receive `len` bytes, acknowledging all but the last one
+    ///
+    /// Must be called right after [`Self::send_address`] as the ADDR flag is cleared here.
+    /// ACK has to be cleared before the last byte has been shifted in, and the point where
+    /// that can be done safely depends on the length of the transfer:
+    ///
+    /// - 1 byte: ACK is cleared before clearing ADDR, the ending is requested right away
+    ///   and the byte is read once RXNE is set.
+    /// - 2 bytes: POS is set before clearing ADDR so that clearing ACK applies to the second
+    ///   byte. Once BTF is set both bytes have arrived, the ending is requested and both are read.
+    /// - N bytes: bytes are read on RXNE until 3 are left. Once BTF is set (N-2 in DATAR,
+    ///   N-1 in the shift register) ACK is cleared and N-2 is read. On the next BTF the
+    ///   ending is requested and the last two bytes are read.
+    ///
+    /// ACK is enabled again once done.
+    fn read_bytes<'b>(
+        &mut self,
+        len: usize,
+        mut bytes: impl Iterator<Item = &'b mut u8>,
+        ending: Ending,
+    ) -> Result<(), Error> {
+        let mut read_byte = |i2c: &I2C1| {
+            let byte = i2c.datar.read().datar().bits();
+            if let Some(b) = bytes.next() {
+                *b = byte;
+            }
+        };
 
-        self.check_error()
+        match len {
+            0 | 1 => {
+                self.i2c.ctlr1.modify(|_, w| w.ack().clear_bit());
+                self.clear_addr();
+                self.send_ending(ending);
+
+                self.wait_while(|s1, _| s1.rx_ne().bit_is_clear())?;
+                read_byte(&self.i2c);
+            }
+            2 => {
+                self.i2c
+                    .ctlr1
+                    .modify(|_, w| w.pos().set_bit().ack().clear_bit());
+                self.clear_addr();
+
+                self.wait_while(|s1, _| s1.btf().bit_is_clear())?;
+                self.send_ending(ending);
+                read_byte(&self.i2c);
+                read_byte(&self.i2c);
+
+                self.i2c.ctlr1.modify(|_, w| w.pos().clear_bit());
+            }
+            _ => {
+                self.clear_addr();
+
+                for _ in 0..len - 3 {
+                    self.wait_while(|s1, s2| {
+                        s1.rx_ne().bit_is_clear()
+                            || s2.msl().bit_is_clear()
+                            || s2.busy().bit_is_clear()
+                    })?;
+                    read_byte(&self.i2c);
+                }
+
+                // N-2 in DATAR, N-1 in the shift register
+                self.wait_while(|s1, _| s1.btf().bit_is_clear())?;
+                self.i2c.ctlr1.modify(|_, w| w.ack().clear_bit());
+                read_byte(&self.i2c);
+
+                // N-1 in DATAR, N in the shift register
+                self.wait_while(|s1, _| s1.btf().bit_is_clear())?;
+                self.send_ending(ending);
+                read_byte(&self.i2c);
+                read_byte(&self.i2c);
+            }
+        }
+
+        // Enable acknowledgements again for the next transfer
+        self.i2c.ctlr1.modify(|_, w| w.ack().set_bit());
+
+        Ok(())
     }
-}
 
-impl<Scl, Sda> embedded_hal_02::blocking::i2c::Read for I2c<Scl, Sda>
-where
-    (Scl, Sda): I2C1Pair,
-{
-    type Error = Error;
+    /// Run a set of operations as a single transaction, ending it with a STOP
+    ///
+    /// Consecutive operations of the same direction are merged, a repeated START
+    /// is only issued when the direction changes.
+    fn transaction_inner<O: TransactionOp>(
+        &mut self,
+        address: Address,
+        operations: &mut [O],
+    ) -> Result<(), Error> {
+        // Wait till idle
+        self.wait_while(|_, s2| s2.busy().bit_is_set())?;
 
-    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        // Send new start event// Wait till idle
-        self.wait_while(|_, s2| s2.busy().bit_is_set());
+        let mut start_requested = false;
+        let mut i = 0;
+        while i < operations.len() {
+            // Find the end of the group of operations going in the same direction
+            let read = operations[i].is_read();
+            let mut end = i + 1;
+            while end < operations.len() && operations[end].is_read() == read {
+                end += 1;
+            }
+            let last = end == operations.len();
+            let group = &mut operations[i..end];
 
-        self.i2c.ctlr1.modify(|_, w| w.start().set_bit());
+            self.send_start(start_requested)?;
+            self.send_address(address, read)?;
 
-        // Wait till start has been sent and master mode is assigned
-        self.wait_while(|s1, s2| {
-            s1.sb().bit_is_clear() || s2.busy().bit_is_clear() || s2.msl().bit_is_clear()
-        });
+            if read {
+                let len = group.iter().map(|op| op.read_len()).sum();
+                let bytes = group.iter_mut().flat_map(|op| op.read_buffer().iter_mut());
 
-        // Send address + read flag
-        self.i2c
-            .datar
-            .write(|w| w.datar().variant((address << 1) | 0b1));
+                let ending = if last { Ending::Stop } else { Ending::Restart };
+                self.read_bytes(len, bytes, ending)?;
+                start_requested = ending == Ending::Restart;
+            } else {
+                self.clear_addr();
+                for op in group {
+                    self.write_bytes(op.write_buffer())?;
+                }
 
-        // Wait address is till sent
-        self.wait_while(|s1, s2| {
-            s1.addr().bit_is_clear() || s2.busy().bit_is_clear() || s2.msl().bit_is_clear()
-        });
+                if last {
+                    self.send_stop();
+                }
+                start_requested = false;
+            }
 
-        // Send each byte one by one
-        for byte in buffer {
-            self.wait_while(|s1, s2| {
-                s1.rx_ne().bit_is_clear() || s2.msl().bit_is_clear() || s2.busy().bit_is_clear()
-            });
-            *byte = self.i2c.datar.read().datar().bits();
+            i = end;
         }
 
-        // Stop transmission
-        self.i2c.ctlr1.modify(|_, w| w.stop().set_bit());
+        Ok(())
+    }
 
-        self.check_error()
+    /// Run a transaction, releasing the bus if anything goes wrong
+    ///
+    /// After losing arbitration the hardware has already given up mastership of the bus.
+    /// The transaction is retried once the other master is done with the bus, up to
+    /// [`I2cConfig::arbitration_retries`] times.
+    fn transaction_at<O: TransactionOp>(
+        &mut self,
+        address: Address,
+        operations: &mut [O],
+    ) -> Result<(), Error> {
+        let mut retries = self.arbitration_retries;
+        loop {
+            match self.transaction_inner(address, operations) {
+                Err(Error::ArbitrationLost) => {
+                    // ARLO is cleared by writing 0, restore ACK in case a receive was aborted
+                    self.i2c.star1.modify(|_, w| w.arlo().clear_bit());
+                    self.i2c
+                        .ctlr1
+                        .modify(|_, w| w.ack().set_bit().pos().clear_bit());
+
+                    if retries == 0 {
+                        return Err(Error::ArbitrationLost);
+                    }
+                    retries -= 1;
+                }
+                result => return self.end_transaction(result),
+            }
+        }
     }
-}
 
-impl<Scl, Sda> embedded_hal_02::blocking::i2c::WriteRead for I2c<Scl, Sda>
-where
-    (Scl, Sda): I2C1Pair,
-{
-    type Error = Error;
+    /// Release the bus if a transaction failed, otherwise check for errors
+    fn end_transaction(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        if let Err(e) = result {
+            // Release the bus if something went wrong, restoring ACK in case a receive was aborted
+            self.i2c
+                .ctlr1
+                .modify(|_, w| w.stop().set_bit().ack().set_bit().pos().clear_bit());
+            return Err(e);
+        }
 
-    fn write_read(
+        self.check_error()
+    }
+
+    fn write_counted_inner(
         &mut self,
         address: u8,
         bytes: &[u8],
-        buffer: &mut [u8],
-    ) -> Result<(), Self::Error> {
-        embedded_hal_02::blocking::i2c::Write::write(self, address, bytes)?;
-        embedded_hal_02::blocking::i2c::Read::read(self, address, buffer)
-    }
-}
+        count: &mut usize,
+    ) -> Result<(), Error> {
+        self.wait_while(|_, s2| s2.busy().bit_is_set())?;
+        self.send_start(false)?;
+        self.send_address(Address::SevenBit(address), false)?;
+        self.clear_addr();
+
+        for byte in bytes {
+            self.wait_while(|s1, _| s1.tx_e().bit_is_clear())?;
+            self.i2c.datar.write(|w| w.datar().variant(*byte));
+
+            // Wait for the byte to go out, or for the error that stopped it
+            self.wait_while(|s1, _| {
+                s1.btf().bit_is_clear()
+                    && s1.af().bit_is_clear()
+                    && s1.arlo().bit_is_clear()
+                    && s1.berr().bit_is_clear()
+            })?;
+            self.check_error()?;
+            *count += 1;
+        }
+
+        self.send_stop();
+        Ok(())
+    }
+
+    fn read_counted_inner(
+        &mut self,
+        address: u8,
+        buffer: &mut [u8],
+        count: &mut usize,
+    ) -> Result<(), Error> {
+        self.wait_while(|_, s2| s2.busy().bit_is_set())?;
+        self.send_start(false)?;
+        self.send_address(Address::SevenBit(address), true)?;
+
+        let len = buffer.len();
+        self.read_bytes(
+            len,
+            buffer.iter_mut().inspect(|_| *count += 1),
+            Ending::Stop,
+        )
+    }
+
+    fn write_pec_inner(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.wait_while(|_, s2| s2.busy().bit_is_set())?;
+        self.send_start(false)?;
+        self.send_address(Address::SevenBit(address), false)?;
+        self.clear_addr();
+
+        if self.i2c.ctlr1.read().enpec().bit_is_set() {
+            for byte in bytes {
+                self.wait_while(|s1, _| s1.tx_e().bit_is_clear())?;
+                self.i2c.datar.write(|w| w.datar().variant(*byte));
+            }
+
+            // The PEC is sent once the last byte has moved to the shift register
+            self.wait_while(|s1, _| s1.tx_e().bit_is_clear())?;
+            self.i2c.ctlr1.modify(|_, w| w.pec().set_bit());
+            self.wait_while(|s1, _| s1.btf().bit_is_clear())?;
+        } else {
+            let pec = smbus_pec(smbus_pec(0, &[address << 1]), bytes);
+            self.write_bytes(bytes)?;
+            self.write_bytes(&[pec])?;
+        }
+
+        self.send_stop();
+        Ok(())
+    }
+
+    fn read_pec_inner(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.wait_while(|_, s2| s2.busy().bit_is_set())?;
+        self.send_start(false)?;
+        self.send_address(Address::SevenBit(address), true)?;
+
+        let mut pec = 0;
+        self.read_bytes(
+            buffer.len() + 1,
+            buffer.iter_mut().chain(core::iter::once(&mut pec)),
+            Ending::Stop,
+        )?;
+
+        if pec != smbus_pec(smbus_pec(0, &[(address << 1) | 1]), buffer) {
+            return Err(Error::PecError);
+        }
+        Ok(())
+    }
+}
+
+/// Common view of the embedded-hal 0.2 and 1.0 transaction operations
+trait TransactionOp {
+    fn is_read(&self) -> bool;
+
+    /// Length of the buffer of a read, 0 for writes
+    fn read_len(&self) -> usize;
+
+    /// Buffer of a read, empty for writes
+    fn read_buffer(&mut self) -> &mut [u8];
+
+    /// Bytes of a write, empty for reads
+    fn write_buffer(&self) -> &[u8];
+}
+
+macro_rules! transaction_op {
+    ($($Operation:ty,)+) => {
+        $(
+            impl TransactionOp for $Operation {
+                fn is_read(&self) -> bool {
+                    matches!(self, Self::Read(_))
+                }
+
+                fn read_len(&self) -> usize {
+                    match self {
+                        Self::Read(buffer) => buffer.len(),
+                        Self::Write(_) => 0,
+                    }
+                }
+
+                fn read_buffer(&mut self) -> &mut [u8] {
+                    match self {
+                        Self::Read(buffer) => buffer,
+                        Self::Write(_) => &mut [],
+                    }
+                }
+
+                fn write_buffer(&self) -> &[u8] {
+                    match self {
+                        Self::Read(_) => &[],
+                        Self::Write(bytes) => bytes,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+transaction_op! {
+    embedded_hal_1::i2c::Operation<'_>,
+    embedded_hal_02::blocking::i2c::Operation<'_>,
+}
+
+/// SMBus CRC-8 (x^8 + x^2 + x + 1) of `bytes`, continuing from `crc`
+fn smbus_pec(crc: u8, bytes: &[u8]) -> u8 {
+    bytes.iter().fold(crc, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Event terminating a receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ending {
+    /// Release the bus
+    Stop,
+    /// Keep the bus and turn around for the next operation
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    BusError,
+    AcknowledgeFailure,
+    ArbitrationLost,
+    Overrun,
+    /// Gave up waiting for the bus, see [`I2cConfig::timeout`]
+    Timeout,
+    /// The received SMBus PEC didn't match the received bytes
+    PecError,
+}
+
+impl embedded_hal_1::i2c::Error for Error {
+    fn kind(&self) -> embedded_hal_1::i2c::ErrorKind {
+        use embedded_hal_1::i2c::{ErrorKind, NoAcknowledgeSource};
+
+        match self {
+            Error::BusError => ErrorKind::Bus,
+            Error::AcknowledgeFailure => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+            Error::ArbitrationLost => ErrorKind::ArbitrationLoss,
+            Error::Overrun => ErrorKind::Overrun,
+            Error::Timeout => ErrorKind::Other,
+            Error::PecError => ErrorKind::Other,
+        }
+    }
+}
+
+impl<Scl, Sda> embedded_hal_1::i2c::ErrorType for I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+}
+
+impl<Scl, Sda> embedded_hal_1::i2c::I2c for I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal_1::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_at(Address::SevenBit(address), operations)
+    }
+}
+
+impl<Scl, Sda> embedded_hal_02::blocking::i2c::Write for I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+
+    #[inline(never)]
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        embedded_hal_1::i2c::I2c::write(self, address, bytes)
+    }
+}
+
+impl<Scl, Sda> embedded_hal_02::blocking::i2c::Read for I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        embedded_hal_1::i2c::I2c::read(self, address, buffer)
+    }
+}
+
+impl<Scl, Sda> embedded_hal_02::blocking::i2c::WriteRead for I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        embedded_hal_1::i2c::I2c::write_read(self, address, bytes, buffer)
+    }
+}
+
+impl<Scl, Sda> embedded_hal_02::blocking::i2c::Transactional for I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+
+    fn exec(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal_02::blocking::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_at(Address::SevenBit(address), operations)
+    }
+}
+
+/// Interrupt driven I2C master writes and reads
+///
+/// Instead of spinning on the status flags, a transfer is started with [`Self::start_write`]
+/// or [`Self::start_read`] and advanced by calling [`Self::on_interrupt`] from both the
+/// `I2C1_EV` and `I2C1_ER` interrupt handlers, leaving the CPU free (or asleep) in between.
+///
+/// The result of a finished transfer is kept until it is collected with [`Self::poll`], no
+/// new transfer can be started before that.
+pub struct I2cInterrupt<Scl, Sda> {
+    i2c: I2c<Scl, Sda>,
+    bytes: &'static [u8],
+    buffer: &'static mut [u8],
+    state: State,
+}
+
+/// Progress of an interrupt driven transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// No transfer started
+    Idle,
+    /// Waiting for the START to be sent
+    Start { address: u8, direction: Direction },
+    /// Waiting for the address to be acknowledged
+    Address { direction: Direction },
+    /// Sending the remaining bytes
+    Write,
+    /// Receiving the remaining bytes
+    Read,
+    /// Transfer finished, result not yet collected
+    Done(Result<(), Error>),
+}
+
+impl<Scl, Sda> I2cInterrupt<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    /// Switch an initialised I2C peripheral over to interrupt driven transfers
+    pub fn new(i2c: I2c<Scl, Sda>) -> Self {
+        Self {
+            i2c,
+            bytes: &[],
+            buffer: &mut [],
+            state: State::Idle,
+        }
+    }
+
+    /// Go back to blocking transfers
+    pub fn release(mut self) -> I2c<Scl, Sda> {
+        self.listen(false);
+        self.i2c
+    }
+
+    /// Start writing `bytes` to the device at `address`
+    ///
+    /// Returns `WouldBlock` while a previous transfer is still in progress, its result has not
+    /// been collected with [`Self::poll`] yet or the bus is busy.
+    pub fn start_write(&mut self, address: u8, bytes: &'static [u8]) -> nb::Result<(), Error> {
+        self.start(address, Direction::Write)?;
+        self.bytes = bytes;
+
+        Ok(())
+    }
+
+    /// Start reading from the device at `address` until `buffer` is full
+    ///
+    /// Returns `WouldBlock` while a previous transfer is still in progress, its result has not
+    /// been collected with [`Self::poll`] yet or the bus is busy.
+    pub fn start_read(&mut self, address: u8, buffer: &'static mut [u8]) -> nb::Result<(), Error> {
+        self.start(address, Direction::Read)?;
+        self.buffer = buffer;
+
+        Ok(())
+    }
+
+    /// Completion flag, true once the last transfer has finished (or none was started)
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Idle | State::Done(_))
+    }
+
+    /// Collect the result of the last transfer, `WouldBlock` while it is in progress
+    ///
+    /// Must be called once a transfer is done before the next one can be started.
+    pub fn poll(&mut self) -> nb::Result<(), Error> {
+        match self.state {
+            State::Done(result) => {
+                self.state = State::Idle;
+                result.map_err(nb::Error::Other)
+            }
+            State::Idle => Ok(()),
+            _ => Err(nb::Error::WouldBlock),
+        }
+    }
+
+    /// Advance the transfer, to be called from the `I2C1_EV` and `I2C1_ER` interrupt handlers
+    pub fn on_interrupt(&mut self) {
+        if let Err(e) = self.i2c.check_error() {
+            self.i2c.clear_error_flags();
+            self.finish(Err(e));
+            return;
+        }
+
+        let s1 = self.i2c.i2c.star1.read();
+
+        match self.state {
+            State::Start { address, direction } if s1.sb().bit_is_set() => {
+                let address = match direction {
+                    Direction::Write => address << 1,
+                    Direction::Read => (address << 1) | 1,
+                };
+                self.i2c.i2c.datar.write(|w| w.datar().variant(address));
+                self.state = State::Address { direction };
+            }
+            State::Address {
+                direction: Direction::Write,
+            } if s1.addr().bit_is_set() => {
+                // Reading STAR2 after STAR1 clears ADDR
+                let _ = self.i2c.i2c.star2.read();
+
+                if self.bytes.is_empty() {
+                    self.finish(Ok(()));
+                } else {
+                    self.state = State::Write;
+                }
+            }
+            State::Address {
+                direction: Direction::Read,
+            } if s1.addr().bit_is_set() => {
+                let i2c = &self.i2c.i2c;
+
+                // Same sequences as the blocking reads, ACK and STOP have to be set up before
+                // the bytes they apply to are received
+                match self.buffer.len() {
+                    0 | 1 => {
+                        i2c.ctlr1.modify(|_, w| w.ack().clear_bit());
+                        let _ = i2c.star2.read();
+                        self.i2c.send_stop();
+                    }
+                    2 => {
+                        i2c.ctlr1.modify(|_, w| w.pos().set_bit().ack().clear_bit());
+                        let _ = i2c.star2.read();
+                        i2c.ctlr2.modify(|_, w| w.itbufen().clear_bit());
+                    }
+                    3 => {
+                        let _ = i2c.star2.read();
+                        i2c.ctlr2.modify(|_, w| w.itbufen().clear_bit());
+                    }
+                    _ => {
+                        let _ = i2c.star2.read();
+                    }
+                }
+
+                self.state = State::Read;
+            }
+            State::Write if s1.tx_e().bit_is_set() => {
+                if let Some((byte, rest)) = self.bytes.split_first() {
+                    self.i2c.i2c.datar.write(|w| w.datar().variant(*byte));
+                    self.bytes = rest;
+
+                    if rest.is_empty() {
+                        // The buffer interrupt would keep firing from now on, only wait for BTF
+                        self.i2c.i2c.ctlr2.modify(|_, w| w.itbufen().clear_bit());
+                    }
+                } else if s1.btf().bit_is_set() {
+                    self.finish(Ok(()));
+                }
+            }
+            State::Read => match self.buffer.len() {
+                // STOP was already requested when ADDR was cleared
+                0 | 1 if s1.rx_ne().bit_is_set() => {
+                    self.read_byte();
+                    self.complete(Ok(()));
+                }
+                // N-1 in DATAR, N in the shift register
+                2 if s1.btf().bit_is_set() => {
+                    self.i2c.send_stop();
+                    self.read_byte();
+                    self.read_byte();
+                    self.complete(Ok(()));
+                }
+                // N-2 in DATAR, N-1 in the shift register
+                3 if s1.btf().bit_is_set() => {
+                    self.i2c.i2c.ctlr1.modify(|_, w| w.ack().clear_bit());
+                    self.read_byte();
+                }
+                n if n > 3 && s1.rx_ne().bit_is_set() => {
+                    self.read_byte();
+
+                    if self.buffer.len() == 3 {
+                        // Only wait for BTF from now on, the last bytes are handled in pairs
+                        self.i2c.i2c.ctlr2.modify(|_, w| w.itbufen().clear_bit());
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Take a transfer from `State::Idle` to `State::Start` and request the START
+    fn start(&mut self, address: u8, direction: Direction) -> nb::Result<(), Error> {
+        if self.state != State::Idle || self.i2c.i2c.star2.read().busy().bit_is_set() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.bytes = &[];
+        self.buffer = &mut [];
+        self.state = State::Start { address, direction };
+
+        self.listen(true);
+        self.i2c.i2c.ctlr1.modify(|_, w| w.start().set_bit());
+
+        Ok(())
+    }
+
+    /// Store the received byte at the front of the buffer, dropping it once the buffer is full
+    fn read_byte(&mut self) {
+        let byte = self.i2c.i2c.datar.read().datar().bits();
+
+        if let Some((b, rest)) = core::mem::take(&mut self.buffer).split_first_mut() {
+            *b = byte;
+            self.buffer = rest;
+        }
+    }
+
+    /// Enable or disable the event, buffer and error interrupts
+    fn listen(&mut self, enable: bool) {
+        self.i2c.i2c.ctlr2.modify(|_, w| {
+            w.itevten()
+                .bit(enable)
+                .itbufen()
+                .bit(enable)
+                .iterren()
+                .bit(enable)
+        });
+    }
+
+    /// Release the bus and stop listening
+    fn finish(&mut self, result: Result<(), Error>) {
+        self.i2c.send_stop();
+        self.complete(result);
+    }
+
+    /// Stop listening once the STOP has been requested
+    fn complete(&mut self, result: Result<(), Error>) {
+        self.listen(false);
+        // Enable acknowledgements again for the next transfer
+        self.i2c
+            .i2c
+            .ctlr1
+            .modify(|_, w| w.pos().clear_bit().ack().set_bit());
+        self.state = State::Done(result);
+    }
+}
+
+/// Direction of a transfer, as seen from the master
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The master sends bytes, see [`SlaveHandler::on_receive`]
+    Write,
+    /// The master reads bytes, see [`SlaveHandler::on_request`]
+    Read,
+}
+
+/// Application side of an I2C slave, driven by [`I2cSlave::on_interrupt`]
+pub trait SlaveHandler {
+    /// The master addressed this device at `address`, which is 0x00 for a general call
+    fn on_address_match(&mut self, address: u8, direction: Direction);
+
+    /// The master sent `byte`
+    fn on_receive(&mut self, byte: u8);
+
+    /// The master wants the next byte
+    ///
+    /// Returning `None` keeps SCL stretched (low) until the byte is handed over
+    /// with [`I2cSlave::respond`].
+    fn on_request(&mut self) -> Option<u8>;
+
+    /// The master ended the transfer with a STOP, or by not acknowledging the last byte it read
+    fn on_stop(&mut self) {}
+
+    /// A bus error occurred, the flags have already been cleared
+    fn on_error(&mut self, _error: Error) {}
+}
+
+/// Addresses an [`I2cSlave`] responds to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlaveConfig {
+    /// Primary 7-bit own address (OADDR1)
+    pub address: u8,
+    /// Second 7-bit own address (OADDR2, ENDUAL)
+    pub secondary_address: Option<u8>,
+    /// Respond to the general call address 0x00 (ENGC)
+    pub general_call: bool,
+    /// Never hold SCL low (NOSTRETCH)
+    ///
+    /// Useful with masters that don't support clock stretching, but every event then has
+    /// to be handled within a bit time: a late [`SlaveHandler::on_request`] sends a stale
+    /// byte and a late [`SlaveHandler::on_receive`] loses one, both reported as
+    /// [`Error::Overrun`]. Returning `None` from `on_request` can't delay the master either.
+    pub disable_clock_stretch: bool,
+}
+
+impl SlaveConfig {
+    /// Only respond to `address`
+    #[inline]
+    pub const fn new(address: u8) -> SlaveConfig {
+        Self {
+            address,
+            secondary_address: None,
+            general_call: false,
+            disable_clock_stretch: false,
+        }
+    }
+}
+
+/// I2C1 running as a slave with 7-bit own addresses
+///
+/// Events are handled by calling [`Self::on_interrupt`] from both the `I2C1_EV` and the
+/// `I2C1_ER` interrupt handlers. Unless [`SlaveConfig::disable_clock_stretch`] is set, the
+/// peripheral holds SCL low until the address match has been handled and while no data is
+/// available to send.
+pub struct I2cSlave<Scl, Sda> {
+    i2c: I2c<Scl, Sda>,
+    config: SlaveConfig,
+    stretching: bool,
+}
+
+impl<Scl, Sda> I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    /// Initialise the I2C1 peripheral as a slave answering to the addresses in `slave`
+    ///
+    /// `config.speed` is only used to configure the timings of the peripheral, the bus
+    /// clock is generated by the master.
+    #[allow(clippy::too_many_arguments)]
+    pub fn i2c1_slave(
+        i2c: I2C1,
+        scl: Scl,
+        sda: Sda,
+        config: I2cConfig,
+        slave: SlaveConfig,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> I2cSlave<Scl, Sda>
+    where
+        Scl: PinExt,
+        Sda: PinExt,
+    {
+        let i2c = Self::i2c1(i2c, scl, sda, config, pcfr, rcc, clocks);
+
+        i2c.i2c.oaddr1.write(|w| {
+            w.addmode()
+                .clear_bit() // 7-bit address
+                .add7_1()
+                .variant(slave.address)
+        });
+        i2c.i2c.oaddr2.write(|w| {
+            w.endual()
+                .bit(slave.secondary_address.is_some())
+                .add2()
+                .variant(slave.secondary_address.unwrap_or(0))
+        });
+        i2c.i2c.ctlr1.modify(|_, w| {
+            w.nostretch()
+                .bit(slave.disable_clock_stretch)
+                .engc()
+                .bit(slave.general_call)
+                .ack()
+                .set_bit()
+        });
+
+        let mut slave = I2cSlave {
+            i2c,
+            config: slave,
+            stretching: false,
+        };
+        slave.listen(true);
+        slave
+    }
+}
+
+impl<Scl, Sda> I2cSlave<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    /// Stop responding to the own address and go back to a master
+    pub fn release(mut self) -> I2c<Scl, Sda> {
+        self.listen(false);
+        self.i2c.i2c.oaddr1.reset();
+        self.i2c.i2c.oaddr2.reset();
+        self.i2c.i2c.ctlr1.modify(|_, w| w.engc().clear_bit());
+        self.i2c
+    }
+
+    /// Whether SCL is being held low waiting for [`Self::respond`]
+    pub fn is_stretching(&self) -> bool {
+        self.stretching
+    }
+
+    /// Send `byte` after [`SlaveHandler::on_request`] returned `None`, releasing SCL
+    pub fn respond(&mut self, byte: u8) {
+        if self.stretching {
+            self.i2c.i2c.datar.write(|w| w.datar().variant(byte));
+            self.stretching = false;
+            self.listen(true);
+        }
+    }
+
+    /// Handle pending events, to be called from the `I2C1_EV` and `I2C1_ER` interrupt handlers
+    pub fn on_interrupt(&mut self, handler: &mut impl SlaveHandler) {
+        let i2c = &self.i2c.i2c;
+        let s1 = i2c.star1.read();
+
+        if s1.af().bit_is_set() {
+            // The master is done reading, this ends the transfer
+            i2c.star1.modify(|_, w| w.af().clear_bit());
+            handler.on_stop();
+            return;
+        }
+
+        if let Err(e) = self.i2c.check_error() {
+            self.i2c.clear_error_flags();
+            handler.on_error(e);
+            return;
+        }
+
+        if s1.addr().bit_is_set() {
+            // Reading STAR2 after STAR1 clears ADDR and releases SCL
+            let s2 = i2c.star2.read();
+            let direction = if s2.tra().bit_is_set() {
+                Direction::Read
+            } else {
+                Direction::Write
+            };
+            let address = if s2.gencall().bit_is_set() {
+                0x00
+            } else if s2.dualf().bit_is_set() {
+                self.config.secondary_address.unwrap_or(self.config.address)
+            } else {
+                self.config.address
+            };
+            handler.on_address_match(address, direction);
+        } else if s1.rx_ne().bit_is_set() {
+            handler.on_receive(i2c.datar.read().datar().bits());
+        } else if s1.tx_e().bit_is_set() {
+            match handler.on_request() {
+                Some(byte) => i2c.datar.write(|w| w.datar().variant(byte)),
+                None => {
+                    // TXE and BTF stay set, the event interrupt would fire until DATAR is written
+                    self.stretching = true;
+                    self.i2c
+                        .i2c
+                        .ctlr2
+                        .modify(|_, w| w.itevten().clear_bit().itbufen().clear_bit());
+                }
+            }
+        } else if s1.stopf().bit_is_set() {
+            // STOPF is cleared by reading STAR1 followed by a write to CTLR1
+            i2c.ctlr1.modify(|_, w| w);
+            handler.on_stop();
+        }
+    }
+
+    /// Enable or disable the event, buffer and error interrupts
+    fn listen(&mut self, enable: bool) {
+        self.i2c.i2c.ctlr2.modify(|_, w| {
+            w.itevten()
+                .bit(enable)
+                .itbufen()
+                .bit(enable)
+                .iterren()
+                .bit(enable)
+        });
+    }
+}
+
+/// I2C1 transmit half of a DMA transfer, see [`I2c::write_dma`]
+pub struct I2cTxDma<Scl, Sda> {
+    i2c: I2c<Scl, Sda>,
+    channel: dma::C6,
+    result: Result<(), Error>,
+    empty: bool,
+}
+
+/// I2C1 receive half of a DMA transfer, see [`I2c::read_dma`]
+pub struct I2cRxDma<Scl, Sda> {
+    i2c: I2c<Scl, Sda>,
+    channel: dma::C7,
+    result: Result<(), Error>,
+    empty: bool,
+}
+
+impl<Scl, Sda> I2c<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    /// Write `buffer` to the device at `address`, moving the data with DMA channel 6
+    ///
+    /// The START and address phase are blocking, the data phase runs in the background.
+    /// The STOP is issued once the transfer has completed, when calling [`Transfer::wait`].
+    /// An empty `buffer` only addresses the device, the transfer is done right away.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 bytes.
+    pub fn write_dma(
+        mut self,
+        mut channel: dma::C6,
+        address: u8,
+        buffer: &'static [u8],
+    ) -> Transfer<&'static [u8], I2cTxDma<Scl, Sda>> {
+        channel.stop();
+        channel.configure(
+            dma::Direction::MemoryToPeripheral,
+            dma::WordSize::Bits8,
+            false,
+        );
+        channel.set_peripheral_address(self.i2c.datar.as_ptr() as u32, false);
+        channel.set_memory_address(buffer.as_ptr() as u32, true);
+        channel.set_transfer_length(buffer.len());
+
+        let empty = buffer.is_empty();
+        let result = self.start_dma(Address::SevenBit(address), false);
+        if result.is_ok() {
+            // A DMA transfer of length 0 would never complete
+            if !empty {
+                self.i2c.ctlr2.modify(|_, w| w.dmaen().set_bit());
+                channel.start();
+            }
+            self.clear_addr();
+        }
+
+        Transfer::new(
+            buffer,
+            I2cTxDma {
+                i2c: self,
+                channel,
+                result,
+                empty,
+            },
+        )
+    }
+
+    /// Fill `buffer` with bytes read from the device at `address`, using DMA channel 7
+    ///
+    /// The START and address phase are blocking, the data phase runs in the background.
+    /// The last byte is NACKed by the hardware and the STOP is issued once the transfer
+    /// has completed, when calling [`Transfer::wait`]. An empty `buffer` only addresses the
+    /// device, the byte it starts to send is NACKed and the transfer is done right away.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 bytes.
+    pub fn read_dma(
+        mut self,
+        mut channel: dma::C7,
+        address: u8,
+        buffer: &'static mut [u8],
+    ) -> Transfer<&'static mut [u8], I2cRxDma<Scl, Sda>> {
+        channel.stop();
+        channel.configure(
+            dma::Direction::PeripheralToMemory,
+            dma::WordSize::Bits8,
+            false,
+        );
+        channel.set_peripheral_address(self.i2c.datar.as_ptr() as u32, false);
+        channel.set_memory_address(buffer.as_mut_ptr() as u32, true);
+        channel.set_transfer_length(buffer.len());
+
+        let result = self.start_dma(Address::SevenBit(address), true);
+        if result.is_ok() {
+            if buffer.is_empty() {
+                // A DMA transfer of length 0 would never complete
+                self.i2c.ctlr1.modify(|_, w| w.ack().clear_bit());
+                self.clear_addr();
+                self.send_stop();
+            } else if buffer.len() > 1 {
+                // NACK the byte following the second to last DMA request
+                self.i2c
+                    .ctlr2
+                    .modify(|_, w| w.dmaen().set_bit().last().set_bit());
+                channel.start();
+                self.clear_addr();
+            } else {
+                // Too late to use LAST for a single byte, NACK it and request the STOP now
+                self.i2c.ctlr1.modify(|_, w| w.ack().clear_bit());
+                self.i2c.ctlr2.modify(|_, w| w.dmaen().set_bit());
+                channel.start();
+                self.clear_addr();
+                self.send_stop();
+            }
+        }
+
+        let empty = buffer.is_empty();
+        Transfer::new(
+            buffer,
+            I2cRxDma {
+                i2c: self,
+                channel,
+                result,
+                empty,
+            },
+        )
+    }
+
+    /// Wait for the bus, then send the START and address for a DMA transfer
+    fn start_dma(&mut self, address: Address, read: bool) -> Result<(), Error> {
+        let result = self
+            .wait_while(|_, s2| s2.busy().bit_is_set())
+            .and_then(|_| self.send_start(false))
+            .and_then(|_| self.send_address(address, read));
+
+        if result.is_err() {
+            self.send_stop();
+        }
+        result
+    }
+
+    /// Turn off the DMA requests and release the bus after a DMA transfer
+    fn end_dma(&mut self) {
+        self.i2c
+            .ctlr2
+            .modify(|_, w| w.dmaen().clear_bit().last().clear_bit());
+        self.i2c
+            .ctlr1
+            .modify(|_, w| w.stop().set_bit().ack().set_bit());
+    }
+}
+
+macro_rules! i2c_dma_payload {
+    ($($Payload:ident: $CX:ident,)+) => {
+        $(
+            impl<Scl, Sda> $Payload<Scl, Sda>
+            where
+                (Scl, Sda): I2C1Pair,
+            {
+                /// Return the I2C peripheral and the DMA channel once the transfer is over
+                pub fn release(self) -> (I2c<Scl, Sda>, dma::$CX) {
+                    (self.i2c, self.channel)
+                }
+            }
+        )+
+    };
+}
+
+i2c_dma_payload! {
+    I2cTxDma: C6,
+    I2cRxDma: C7,
+}
+
+impl<Scl, Sda> TransferPayload for I2cTxDma<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+
+    fn is_done(&self) -> bool {
+        self.empty
+            || self.result.is_err()
+            || self.channel.is_complete()
+            || self.i2c.check_error().is_err()
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.channel.stop();
+
+        let i2c = &mut self.i2c;
+        let empty = self.empty;
+        let result = self.result.and_then(|_| {
+            // Transfer complete only means the last byte was moved to DATAR, wait for it to go out
+            if !empty {
+                i2c.wait_while(|s1, _| s1.btf().bit_is_clear() && s1.af().bit_is_clear())?;
+            }
+            i2c.check_error()
+        });
+
+        i2c.end_dma();
+        result
+    }
+}
+
+impl<Scl, Sda> TransferPayload for I2cRxDma<Scl, Sda>
+where
+    (Scl, Sda): I2C1Pair,
+{
+    type Error = Error;
+
+    fn is_done(&self) -> bool {
+        self.empty
+            || self.result.is_err()
+            || self.channel.is_complete()
+            || self.i2c.check_error().is_err()
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.channel.stop();
+
+        let result = self.result.and_then(|_| self.i2c.check_error());
+
+        self.i2c.end_dma();
+        result
+    }
+}
 
 /// Marker trait for valid combinations of SCL and SDA for multiplexed I2C pins
-pub trait I2C1Pair {
+///
+/// Remap options 0b10 and 0b11 select the same pins, so there are only three pairs.
+/// This trait is sealed, other pin combinations can't be routed to I2C1.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a valid (SCL, SDA) pin pair for I2C1",
+    note = "valid pairs are (PC2, PC1), (PD1, PD0) and (PC5, PC6), in that order",
+    note = "both pins have to be in alternate open drain mode, see `into_alternate_open_drain()`"
+)]
+pub trait I2C1Pair: crate::Sealed {
     /// High and Low bits of remap register (I2C1REMAP1 and I2C1_RM)
     // TODO: Should this just be u8? Does it matter?
     const REMAP_BITS: (bool, bool);
 }
 
 /// Default pin remapping option (0b00)
-/// # T and U
-/// While Open Drain is recommended, pins can be used in Push-pull configuration as well
-impl<T, U> I2C1Pair for (PC2<Alternate<T>>, PC1<Alternate<U>>) {
+impl I2C1Pair for (PC2<Alternate<OpenDrain>>, PC1<Alternate<OpenDrain>>) {
     const REMAP_BITS: (bool, bool) = (false, false);
 }
 
+impl crate::Sealed for (PC2<Alternate<OpenDrain>>, PC1<Alternate<OpenDrain>>) {}
+
 /// Pin remapping option 2 (0b01)
-/// # T and U
-/// While Open Drain is recommended, pins can be used in Push-pull configuration as well
-impl<T, U> I2C1Pair for (PD1<Alternate<T>>, PD0<Alternate<U>>) {
+impl I2C1Pair for (PD1<Alternate<OpenDrain>>, PD0<Alternate<OpenDrain>>) {
     const REMAP_BITS: (bool, bool) = (false, true);
 }
 
+impl crate::Sealed for (PD1<Alternate<OpenDrain>>, PD0<Alternate<OpenDrain>>) {}
+
 /// Pin remapping option 3 (0b1X)
-/// # T and U
-/// While Open Drain is recommended, pins can be used in Push-pull configuration as well
-impl<T, U> I2C1Pair for (PC5<Alternate<T>>, PC6<Alternate<U>>) {
+impl I2C1Pair for (PC5<Alternate<OpenDrain>>, PC6<Alternate<OpenDrain>>) {
     const REMAP_BITS: (bool, bool) = (true, false);
 }
+
+impl crate::Sealed for (PC5<Alternate<OpenDrain>>, PC6<Alternate<OpenDrain>>) {}
@@ -19,16 +19,20 @@ pub use ch32v0::ch32v003 as pac;
 #[cfg(feature = "rt")]
 use pac::__EXTERNAL_INTERRUPTS as _;
 
+pub mod adc;
+pub mod afio;
 pub mod gpio;
 pub mod pwr;
 pub mod rcc;
 //
 // pub mod pfic;
 pub mod delay;
+pub mod dma;
 pub mod extend;
 pub mod i2c;
 pub mod serial;
 pub mod signature;
+pub mod spi;
 pub mod timer;
 
 mod critical_section;
@@ -4,6 +4,8 @@ pub use crate::U32Ext as _;
 
 pub use crate::rcc::RccExt as _;
 
+pub use crate::afio::AfioExt as _;
+
 pub use crate::serial::UsartExt as _;
 
 pub use crate::gpio::GpioExt as _;
@@ -1,4 +1,7 @@
 //! Reset and clock control.
+//!
+//! This covers the CH32V003 only, which has no USB controller: there is no USB clock (USBPRE)
+//! to configure, and the PLL is a fixed 2x multiplier.
 
 use core::ops::Div;
 
@@ -7,14 +10,18 @@ mod enable;
 use ch32v0::{Readable, Reg, Writable};
 use fugit::{HertzU32 as Hertz, RateExtU32};
 
+use crate::gpio::{gpioc::PC4, Alternate, PushPull};
 use crate::pac::{
     rcc::{self, cfgr0::CFGR0_SPEC},
-    RCC,
+    FLASH, RCC,
 };
 
 /// Typical output frequency of the HSI oscillator.
 const HSI_FREQUENCY: Hertz = Hertz::from_raw(24_000_000);
 
+/// Typical output frequency of the LSI oscillator.
+const LSI_FREQUENCY: Hertz = Hertz::from_raw(128_000);
+
 /// Extension trait that constrains the `RCC` peripheral
 pub trait RccExt {
     /// Constrains the `RCC` peripheral so it plays nicely with the other abstractions
@@ -43,6 +50,84 @@ pub struct Rcc {
     pub config: Config,
 }
 
+/// Cause of the last reset, see [`Rcc::reset_cause`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetCause {
+    /// Low power management reset, entering standby while it is forbidden by the option bytes
+    LowPower,
+    /// Window watchdog timeout
+    WindowWatchdog,
+    /// Independent watchdog timeout
+    IndependentWatchdog,
+    /// Software reset, through PFIC
+    Software,
+    /// Power on or power down reset
+    PowerOn,
+    /// NRST pin pulled low
+    Pin,
+    /// No flag is set, they've been cleared since the last reset
+    Unknown,
+}
+
+impl Rcc {
+    /// Cause of the last reset, read from the flags of RSTSCKR
+    ///
+    /// The flags accumulate until [`Self::clear_reset_flags`] is called, and internal resets
+    /// also drive NRST. When several are set, the most specific cause is returned.
+    pub fn reset_cause(&self) -> ResetCause {
+        // NOTE(unsafe) atomic read with no side effects
+        let r = unsafe { (*RCC::ptr()).rstsckr.read() };
+
+        if r.lpwrrstf().bit_is_set() {
+            ResetCause::LowPower
+        } else if r.wwdgrstf().bit_is_set() {
+            ResetCause::WindowWatchdog
+        } else if r.iwdgrstf().bit_is_set() {
+            ResetCause::IndependentWatchdog
+        } else if r.sftrstf().bit_is_set() {
+            ResetCause::Software
+        } else if r.porrstf().bit_is_set() {
+            ResetCause::PowerOn
+        } else if r.pinrstf().bit_is_set() {
+            ResetCause::Pin
+        } else {
+            ResetCause::Unknown
+        }
+    }
+
+    /// Adjust the HSI frequency with HSITRIM, `value` from 0 to 31
+    ///
+    /// The reset value is 16, higher values speed the oscillator up. This is added to the
+    /// factory calibration, e.g. to tune the HSI against a reference when a USART baud rate
+    /// is out of tolerance.
+    pub fn trim_hsi(&mut self, value: u8) {
+        // NOTE(unsafe) the value is masked to the 5 bits of the field
+        unsafe {
+            (*RCC::ptr())
+                .ctlr
+                .modify(|_, w| w.hsitrim().bits(value & 0x1f))
+        };
+    }
+
+    /// Current HSI trim value, see [`Self::trim_hsi`]
+    pub fn hsi_trim(&self) -> u8 {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*RCC::ptr()).ctlr.read().hsitrim().bits() }
+    }
+
+    /// Factory calibration of the HSI (HSICAL), loaded at reset
+    pub fn hsi_calibration(&self) -> u8 {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*RCC::ptr()).ctlr.read().hsical().bits() }
+    }
+
+    /// Clear the reset flags (RMVF), so the cause of the next reset can be told apart
+    pub fn clear_reset_flags(&mut self) {
+        // NOTE(unsafe) RMVF only clears the reset flags, LSION is kept by the modify
+        unsafe { (*RCC::ptr()).rstsckr.modify(|_, w| w.rmvf().set_bit()) };
+    }
+}
+
 macro_rules! bus_struct {
     ($($busX:ident => ($EN:ident, $en:ident, $($RST:ident, $rst:ident,)? $doc:literal),)+) => {
         $(
@@ -80,6 +165,59 @@ bus_struct! {
     AHB => (AHBPCENR, ahbpcenr, "Advanced High-performance Bus (AHB) registers"),
 }
 
+/// Bus registers found in [`Rcc`]
+pub trait BusAccess: crate::Sealed {
+    /// The registers of this bus in `rcc`
+    fn get(rcc: &mut Rcc) -> &mut Self;
+}
+
+macro_rules! bus_access {
+    ($($busX:ident => $field:ident,)+) => {
+        $(
+            impl crate::Sealed for $busX {}
+            impl BusAccess for $busX {
+                fn get(rcc: &mut Rcc) -> &mut Self {
+                    &mut rcc.$field
+                }
+            }
+        )+
+    };
+}
+
+bus_access! {
+    APB1 => apb1,
+    APB2 => apb2,
+    AHB => ahb,
+}
+
+impl Rcc {
+    /// Enable the clock of peripheral `P`, e.g. `rcc.enable_peripheral::<pac::SPI1>()`
+    ///
+    /// For peripherals without a driver in this crate, used through the PAC.
+    pub fn enable_peripheral<P: Enable>(&mut self)
+    where
+        P::Bus: BusAccess,
+    {
+        P::enable(P::Bus::get(self));
+    }
+
+    /// Disable the clock of peripheral `P`
+    pub fn disable_peripheral<P: Enable>(&mut self)
+    where
+        P::Bus: BusAccess,
+    {
+        P::disable(P::Bus::get(self));
+    }
+
+    /// Reset peripheral `P`, putting all its registers back to their reset values
+    pub fn reset_peripheral<P: Reset>(&mut self)
+    where
+        P::Bus: BusAccess,
+    {
+        P::reset(P::Bus::get(self));
+    }
+}
+
 // clock config
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -88,6 +226,13 @@ pub struct HSEConfig {
     pub source: HSESrc,
 }
 
+impl HSEConfig {
+    /// HSE running at `frequency`, from a crystal or an external clock on PA1 (`source`)
+    pub const fn new(frequency: Hertz, source: HSESrc) -> Self {
+        Self { frequency, source }
+    }
+}
+
 impl Default for HSEConfig {
     fn default() -> Self {
         Self {
@@ -103,6 +248,9 @@ pub enum HSESrc {
     /// Crystal/ceramic resonator
     Crystal,
     /// External clock source, HSE bypassed
+    ///
+    /// For canned oscillators and clocks shared with another chip, fed into PA1 with PA2
+    /// left free.
     Bypass,
 }
 
@@ -118,7 +266,7 @@ impl Default for HSESrc {
 pub enum ClockSrc {
     /// 24MHz internal RC oscillator
     Hsi = 0b00,
-    /// High speed external oscillator, 5-25Mhz
+    /// High speed external oscillator, 4-25Mhz
     Hse = 0b01,
     /// Internal phase locked loop
     Pll = 0b10,
@@ -165,6 +313,31 @@ impl Div<AHBPrescaler> for Hertz {
     }
 }
 
+/// ADC clock prescaler, dividing HCLK
+///
+/// The ADC clock may run up to 24 MHz, which every divider satisfies as HCLK is at most
+/// 48 MHz.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ADCPrescaler {
+    Div2 = 0b00,
+    Div4 = 0b01,
+    Div6 = 0b10,
+    Div8 = 0b11,
+}
+
+impl Div<ADCPrescaler> for Hertz {
+    type Output = Hertz;
+
+    fn div(self, rhs: ADCPrescaler) -> Self::Output {
+        match rhs {
+            ADCPrescaler::Div2 => self / 2,
+            ADCPrescaler::Div4 => self / 4,
+            ADCPrescaler::Div6 => self / 6,
+            ADCPrescaler::Div8 => self / 8,
+        }
+    }
+}
+
 /// Source for the internal phase locked loop
 #[derive(Clone, Copy, PartialEq)]
 #[repr(u8)]
@@ -175,7 +348,7 @@ pub enum PLLSrc {
     Hsi = 0b0,
 }
 
-/// Microcontroller clock output
+/// Microcontroller clock output, on PC4
 ///
 /// Value on reset: None
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -193,6 +366,17 @@ pub enum MCO {
     Pll = 0b111,
 }
 
+/// Invalid clock configuration, see [`Config::try_freeze`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockError {
+    /// HSE or the PLL from HSE is selected, but [`Config::hse`] is `None`
+    MissingHse,
+    /// The HSE frequency is outside of the 4-25MHz range
+    HseOutOfRange,
+    /// The system clock would be above 48MHz
+    SysclkTooHigh,
+}
+
 /// Clock configuration
 #[derive(Clone, Copy)]
 pub struct Config {
@@ -201,11 +385,16 @@ pub struct Config {
     /// Phase locked loop (2x multiplier)
     pub pll: PLLSrc,
     /// Enable internal 128Khz clock. Cannot be used as core clock source
+    ///
+    /// It is needed by the independent watchdog and the auto wake-up timer. There is no LSE
+    /// oscillator (and no RTC) on the CH32V003, the LSI is the only low speed clock.
     pub enable_lsi: bool,
     /// Which clock feeds the core frequency
     pub mux: ClockSrc,
     /// AHB bus frequency prescaler
     pub ahb_pre: AHBPrescaler,
+    /// ADC clock prescaler
+    pub adc_pre: ADCPrescaler,
     /// Clock output configuration
     pub mco: MCO,
 }
@@ -219,6 +408,7 @@ impl Default for Config {
             enable_lsi: false,
             mux: ClockSrc::Hsi,
             ahb_pre: AHBPrescaler::NotDivided,
+            adc_pre: ADCPrescaler::Div2,
             mco: MCO::None,
         }
     }
@@ -229,13 +419,44 @@ impl Config {
     /// and return them via the `Clocks` struct.
     ///
     /// The user shouldn't call freeze more than once as the clocks parameters
-    /// cannot be changed after the clocks have started.
+    /// cannot be changed after the clocks have started, use [`Clocks::reconfigure`] to
+    /// change them at runtime.
     ///
     /// The implementation makes the following choice: HSI is always chosen over
     /// HSE except when HSE is provided. When HSE is provided, HSE is used
     /// wherever it is possible.
+    ///
+    /// # Panics
+    ///
+    /// If the configuration is invalid, see [`Self::try_freeze`].
     pub fn freeze(self) -> Clocks {
+        self.try_freeze().expect("invalid clock configuration")
+    }
+
+    /// Like [`Self::freeze`], but returns an error for an invalid configuration instead of
+    /// panicking, before touching any register
+    ///
+    /// The frequencies in the returned `Clocks` are exact: the PLL is a fixed 2x multiplier
+    /// and the prescalers are plain dividers, so no rounding happens.
+    pub fn try_freeze(self) -> Result<Clocks, ClockError> {
+        let hse = self.hse.map(|hse| hse.frequency);
+        if let Some(frequency) = hse {
+            if frequency < 4.MHz::<1, 1>() || frequency > 25.MHz::<1, 1>() {
+                return Err(ClockError::HseOutOfRange);
+            }
+        }
+        let sysclk = match (self.mux, self.pll) {
+            (ClockSrc::Hsi, _) => HSI_FREQUENCY,
+            (ClockSrc::Pll, PLLSrc::Hsi) => HSI_FREQUENCY * 2,
+            (ClockSrc::Hse, _) => hse.ok_or(ClockError::MissingHse)?,
+            (ClockSrc::Pll, PLLSrc::Hse) => hse.ok_or(ClockError::MissingHse)? * 2,
+        };
+        if sysclk > 48.MHz::<1, 1>() {
+            return Err(ClockError::SysclkTooHigh);
+        }
+
         let rcc = unsafe { &(*RCC::ptr()) };
+        let flash = unsafe { &(*FLASH::ptr()) };
 
         let mut clocks = Clocks::default();
 
@@ -270,6 +491,8 @@ impl Config {
 
         // Configure HSE if provided
         if let Some(hse) = self.hse {
+            // HSEBYP can only be written while HSE is off, the system clock is HSI by now
+            rcc.ctlr.modify(|_, w| w.hseon().clear_bit());
             match hse.source {
                 HSESrc::Crystal => rcc.ctlr.modify(|_, w| w.hsebyp().clear_bit()),
                 HSESrc::Bypass => rcc.ctlr.modify(|_, w| w.hsebyp().set_bit()),
@@ -283,15 +506,22 @@ impl Config {
             clocks.hse = Some(hse.frequency);
         }
 
-        // Configure HCLK
-        // TODO: ADCPRE
-        rcc.cfgr0
-            .modify(|_, w| w.hpre().variant(self.ahb_pre as u8));
+        // Configure HCLK and the ADC clock
+        rcc.cfgr0.modify(|_, w| {
+            w.hpre()
+                .variant(self.ahb_pre as u8)
+                .adcpre()
+                .variant(self.adc_pre as u8)
+        });
 
         // Enable PWR domain
         rcc.apb1pcenr.modify(|_, w| w.pwren().set_bit());
-        // Enable editing backup_domain RCC.BDCTLR
-        // pwr.ctlr.modify(|_, w| w.dbp().set_bit());
+        // The CH32V003 has no backup domain: no BDCTLR, no RTC and so no RTC clock source to
+        // select. Use the auto wake-up timer, clocked by the LSI, for periodic wake-ups.
+
+        // Flash needs 1 wait state above 24 MHz, use it while switching and lower it once the
+        // final frequency is known. There is no prefetch buffer to configure on this part.
+        flash.actlr.modify(|_, w| w.latency().set_bit());
 
         match (self.mux, self.pll) {
             (ClockSrc::Hse, _) => {
@@ -330,8 +560,15 @@ impl Config {
             }
         }
 
-        // Calculate AHB and APB speeds
+        // Calculate AHB and APB speeds, the APB buses have no prescaler and run at HCLK
         clocks.hclk = clocks.sysclk / self.ahb_pre;
+        clocks.pclk1 = clocks.hclk;
+        clocks.pclk2 = clocks.hclk;
+        clocks.adcclk = clocks.hclk / self.adc_pre;
+
+        if clocks.hclk <= 24.MHz::<1, 1>() {
+            flash.actlr.modify(|_, w| w.latency().clear_bit());
+        }
 
         // Configure low speed internal RC (128khz)
         if self.enable_lsi {
@@ -340,6 +577,7 @@ impl Config {
                 |w| w.lsion().set_bit(),
                 |r| r.lsirdy().bit_is_set(),
             );
+            clocks.lsi = Some(LSI_FREQUENCY);
         }
 
         // Enable clock output
@@ -350,7 +588,7 @@ impl Config {
             qingke::riscv::asm::delay(16);
         }
 
-        clocks
+        Ok(clocks)
     }
 }
 
@@ -361,6 +599,9 @@ impl Config {
 pub struct Clocks {
     pub sysclk: Hertz,
     pub hclk: Hertz,
+    pub pclk1: Hertz,
+    pub pclk2: Hertz,
+    pub adcclk: Hertz,
     pub pllclk: Option<Hertz>,
     pub hse: Option<Hertz>,
     pub lsi: Option<Hertz>,
@@ -372,6 +613,21 @@ impl Clocks {
         self.hclk
     }
 
+    /// Returns the frequency of the APB1, the same as HCLK on this part
+    pub fn pclk1(&self) -> Hertz {
+        self.pclk1
+    }
+
+    /// Returns the frequency of the APB2, the same as HCLK on this part
+    pub fn pclk2(&self) -> Hertz {
+        self.pclk2
+    }
+
+    /// Returns the frequency of the ADC clock
+    pub fn adcclk(&self) -> Hertz {
+        self.adcclk
+    }
+
     /// Returns the system (core) frequency
     pub fn sysclk(&self) -> Hertz {
         self.sysclk
@@ -386,6 +642,70 @@ impl Clocks {
     pub fn lsi(&self) -> Option<Hertz> {
         self.lsi
     }
+
+    /// Switch to a new clock configuration, e.g. to run from HSI at low load
+    ///
+    /// The system clock is moved to HSI before the oscillators and the PLL are reprogrammed,
+    /// and the flash wait state is raised during the switch, as done by [`Config::freeze`].
+    ///
+    /// Drivers compute their dividers from the `Clocks` they were given: USART baud rates,
+    /// I2C timings and delays are wrong after this until they are reconfigured, e.g. with
+    /// [`I2c::set_speed`](crate::i2c::I2c::set_speed). Only reconfigure while they are idle.
+    ///
+    /// # Panics
+    ///
+    /// If the configuration is invalid, see [`Self::try_reconfigure`].
+    pub fn reconfigure(self, config: Config) -> Clocks {
+        self.try_reconfigure(config)
+            .expect("invalid clock configuration")
+    }
+
+    /// Like [`Self::reconfigure`], but returns an error for an invalid configuration
+    /// instead of panicking, before touching any register
+    ///
+    /// Once the system clock has switched, the PLL is turned off if the new configuration
+    /// doesn't use it (as system clock or MCO source), and the HSE if none is configured.
+    pub fn try_reconfigure(self, config: Config) -> Result<Clocks, ClockError> {
+        let clocks = config.try_freeze()?;
+
+        // NOTE(unsafe) the clock configuration is owned by the `Clocks` being replaced
+        let rcc = unsafe { &(*RCC::ptr()) };
+        if config.mux != ClockSrc::Pll && config.mco != MCO::Pll {
+            rcc.ctlr.modify(|_, w| w.pllon().clear_bit());
+        }
+        if config.hse.is_none() {
+            rcc.ctlr.modify(|_, w| w.hseon().clear_bit());
+        }
+
+        Ok(clocks)
+    }
+
+    /// Output `source` on the MCO pin, PC4, configured as a 50MHz alternate push pull output
+    ///
+    /// Also returns the output frequency, `None` for [`MCO::None`] or when the selected clock
+    /// isn't running. This replaces the source selected with [`Config::mco`].
+    pub fn enable_mco<MODE>(
+        &self,
+        source: MCO,
+        pin: PC4<MODE>,
+    ) -> (PC4<Alternate<PushPull>>, Option<Hertz>) {
+        // NOTE(unsafe) only the MCO field is modified, it isn't touched after `freeze`
+        unsafe {
+            (*RCC::ptr())
+                .cfgr0
+                .modify(|_, w| w.mco().variant(source as u8))
+        };
+
+        let frequency = match source {
+            MCO::None => None,
+            MCO::Sysclk => Some(self.sysclk),
+            MCO::Hsi => Some(HSI_FREQUENCY),
+            MCO::Hse => self.hse,
+            MCO::Pll => self.pllclk,
+        };
+
+        (pin.into_alternate(), frequency)
+    }
 }
 
 impl Default for Clocks {
@@ -393,6 +713,9 @@ impl Default for Clocks {
         Clocks {
             sysclk: 24.MHz(),
             hclk: 8.MHz(),
+            pclk1: 8.MHz(),
+            pclk2: 8.MHz(),
+            adcclk: 4.MHz(),
             pllclk: None,
             hse: None,
             lsi: None,
@@ -440,13 +763,13 @@ impl BusClock for AHB {
 
 impl BusClock for APB1 {
     fn clock(clocks: &Clocks) -> Hertz {
-        clocks.hclk
+        clocks.pclk1
     }
 }
 
 impl BusClock for APB2 {
     fn clock(clocks: &Clocks) -> Hertz {
-        clocks.hclk
+        clocks.pclk2
     }
 }
 
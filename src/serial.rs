@@ -1,6 +1,8 @@
 //! Universal Synchronous Asynchronous Receiver Transmitter (USART)
 
-use crate::pac::{AFIO, USART1};
+use crate::afio::PCFR;
+use crate::dma::{self, Transfer, TransferPayload};
+use crate::pac::{usart1, USART1};
 use crate::rcc::{BusClock, Clocks, Enable, Rcc, Reset};
 use core::convert::Infallible;
 use core::fmt;
@@ -36,6 +38,9 @@ pub trait Rts<const REMAP: u8> {
     }
 }
 
+/// TX pin in alternate open drain mode, for [`Usart::new_half_duplex`]
+pub trait HalfDuplex<const REMAP: u8> {}
+
 pub struct NoCk {}
 pub struct NoTx {}
 pub struct NoRx {}
@@ -84,17 +89,49 @@ pub enum Error {
     Overrun,
     /// Parity check error
     Parity,
+    /// Bus error during a DMA transfer
+    Dma,
 }
 
+/// Reasons a [`Config`] can't be used
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigError {
+    /// The baud rate is zero, or out of the range of BRR for the peripheral clock
+    InvalidBaudrate,
+    /// The data bits and the parity bit don't make an 8 or 9 bit word
+    InvalidWordLength,
+    /// The achieved baud rate is more than [`MAX_BAUDRATE_ERROR_PERMILLE`] off the requested one
+    BaudrateError,
+}
+
+/// Largest baud rate error accepted by [`UsartExt::try_usart`], in thousandths (2%)
+pub const MAX_BAUDRATE_ERROR_PERMILLE: u32 = 20;
+
 pub trait UsartExt {
+    /// Initialise the USART, panicking if the configuration is invalid
     fn usart<const REMAP: u8, TX: Tx<REMAP>, RX: Rx<REMAP>>(
         self,
         tx: TX,
         rx: RX,
         config: Config,
+        pcfr: &mut PCFR,
         rcc: &mut Rcc,
         clocks: &Clocks,
     ) -> Usart<NoCk, TX, RX, NoCts, NoRts>;
+
+    /// Initialise the USART, checking the configuration before touching the peripheral
+    ///
+    /// Fails with [`ConfigError::BaudrateError`] when the peripheral clock can't generate
+    /// the baud rate within 2%, see [`Config::baudrate_error_permille`].
+    fn try_usart<const REMAP: u8, TX: Tx<REMAP>, RX: Rx<REMAP>>(
+        self,
+        tx: TX,
+        rx: RX,
+        config: Config,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Result<Usart<NoCk, TX, RX, NoCts, NoRts>, ConfigError>;
 }
 
 pub struct Usart<CK, TX, RX, CTS, RTS> {
@@ -112,9 +149,13 @@ impl<CK, TX, RX, CTS, RTS> Usart<CK, TX, RX, CTS, RTS> {
     }
 }
 
+/// Number of data bits, not counting the parity bit
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum DataBits {
+    /// Only with parity, e.g. 7E1. Received words keep the parity bit in bit 7
+    DataBits7,
     DataBits8,
+    /// Only without parity
     DataBits9,
 }
 
@@ -157,6 +198,70 @@ pub struct Config {
     pub parity: Parity,
 }
 
+impl Config {
+    pub fn baudrate(mut self, baudrate: u32) -> Self {
+        self.baudrate = baudrate;
+        self
+    }
+
+    pub fn data_bits(mut self, data_bits: DataBits) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    /// Value of the M bit: the hardware word includes the parity bit, so 8 data bits with
+    /// parity need a 9 bit word
+    fn long_word(&self) -> Result<bool, ConfigError> {
+        let parity = self.parity != Parity::ParityNone;
+        match (self.data_bits, parity) {
+            (DataBits::DataBits7, true) | (DataBits::DataBits8, false) => Ok(false),
+            (DataBits::DataBits8, true) | (DataBits::DataBits9, false) => Ok(true),
+            _ => Err(ConfigError::InvalidWordLength),
+        }
+    }
+
+    /// Baud rate actually generated from the peripheral clock
+    pub fn achieved_baudrate(&self, clocks: &Clocks) -> Result<u32, ConfigError> {
+        let div = self.divider(clocks)?;
+        Ok(USART1::clock(clocks).raw() / div)
+    }
+
+    /// Difference between the achieved and the requested baud rate, in thousandths
+    ///
+    /// Most receivers tolerate 2 to 3% in total. When this is large, pick a baud rate that
+    /// divides the peripheral clock better, or another clock.
+    pub fn baudrate_error_permille(&self, clocks: &Clocks) -> Result<u32, ConfigError> {
+        let achieved = self.achieved_baudrate(clocks)? as u64;
+        let requested = self.baudrate as u64;
+        Ok((achieved.abs_diff(requested) * 1000 / requested) as u32)
+    }
+
+    /// BRR holds USARTDIV = PCLK / (16 * baudrate) as 12.4 fixed point, which is the
+    /// rounded PCLK / baudrate
+    fn divider(&self, clocks: &Clocks) -> Result<u32, ConfigError> {
+        if self.baudrate == 0 {
+            return Err(ConfigError::InvalidBaudrate);
+        }
+        let apbclk = USART1::clock(clocks).raw();
+        let div = (apbclk + self.baudrate / 2) / self.baudrate;
+        if (16..=0xffff).contains(&div) {
+            Ok(div)
+        } else {
+            Err(ConfigError::InvalidBaudrate)
+        }
+    }
+}
+
 impl Default for Config {
     // 115200 8N1
     fn default() -> Self {
@@ -175,50 +280,26 @@ impl UsartExt for USART1 {
         tx: TX,
         rx: RX,
         config: Config,
+        pcfr: &mut PCFR,
         rcc: &mut Rcc,
         clocks: &Clocks,
     ) -> Usart<NoCk, TX, RX, NoCts, NoRts> {
-        let usart = self;
-
-        USART1::enable(&mut rcc.apb2);
-        USART1::reset(&mut rcc.apb2);
-
-        AFIO::enable(&mut rcc.apb2);
-
-        let apbclk = USART1::clock(&clocks).raw();
-        let integer_divider = (25 * apbclk) / (4 * config.baudrate);
-        let div_m = integer_divider / 100;
-        let div_f = integer_divider - 100 * div_m;
-
-        usart.brr.write(|w| {
-            w.div_fraction()
-                .variant(div_f as u8)
-                .div_mantissa()
-                .variant(div_m as u16)
-        });
+        self.try_usart(tx, rx, config, pcfr, rcc, clocks)
+            .expect("invalid USART configuration")
+    }
 
-        let afio = unsafe { &(*AFIO::ptr()) };
+    fn try_usart<const REMAP: u8, TX: Tx<REMAP>, RX: Rx<REMAP>>(
+        self,
+        tx: TX,
+        rx: RX,
+        config: Config,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Result<Usart<NoCk, TX, RX, NoCts, NoRts>, ConfigError> {
+        let usart = self;
 
-        afio.pcfr.modify(|_, w| {
-            w.usart1rm()
-                .bit(REMAP & 0b1 == 1)
-                .usart1remap1()
-                .bit((REMAP & 0b10) >> 1 == 1)
-        });
-
-        // set stop bits
-        usart
-            .ctlr2
-            .modify(|_, w| w.stop().variant(config.stop_bits.to_raw()));
-
-        usart.ctlr1.modify(|_, w| {
-            w.m()
-                .bit(config.data_bits == DataBits::DataBits9)
-                .pce()
-                .bit(config.parity != Parity::ParityNone)
-                .ps()
-                .bit(config.parity == Parity::ParityOdd)
-        });
+        setup(&usart, REMAP, &config, pcfr, rcc, clocks)?;
 
         TX::enable(&usart);
         RX::enable(&usart);
@@ -226,14 +307,93 @@ impl UsartExt for USART1 {
         // enable usart
         usart.ctlr1.modify(|_, w| w.ue().set_bit());
 
-        Usart {
+        Ok(Usart {
             ck: NoCk {},
             tx,
             rx,
             cts: NoCts {},
             rts: NoRts {},
             usart,
-        }
+        })
+    }
+}
+
+/// Common initialisation, up to enabling the transmitter and receiver
+fn setup(
+    usart: &USART1,
+    remap: u8,
+    config: &Config,
+    pcfr: &mut PCFR,
+    rcc: &mut Rcc,
+    clocks: &Clocks,
+) -> Result<(), ConfigError> {
+    let long_word = config.long_word()?;
+    let div = config.divider(clocks)?;
+    if config.baudrate_error_permille(clocks)? > MAX_BAUDRATE_ERROR_PERMILLE {
+        return Err(ConfigError::BaudrateError);
+    }
+
+    USART1::enable(&mut rcc.apb2);
+    USART1::reset(&mut rcc.apb2);
+
+    usart.brr.write(|w| {
+        w.div_fraction()
+            .variant((div & 0xf) as u8)
+            .div_mantissa()
+            .variant((div >> 4) as u16)
+    });
+
+    pcfr.set_usart1_remap(remap);
+
+    // set stop bits
+    usart
+        .ctlr2
+        .modify(|_, w| w.stop().variant(config.stop_bits.to_raw()));
+
+    usart.ctlr1.modify(|_, w| {
+        w.m()
+            .bit(long_word)
+            .pce()
+            .bit(config.parity != Parity::ParityNone)
+            .ps()
+            .bit(config.parity == Parity::ParityOdd)
+    });
+
+    Ok(())
+}
+
+impl<TX> Usart<NoCk, TX, NoRx, NoCts, NoRts> {
+    /// Initialise the USART in single wire half duplex mode (HDSEL), on the TX pin only
+    ///
+    /// The pin has to be an alternate open drain output, with a pull-up on the line. The
+    /// line is released whenever nothing is being sent, so the other devices can answer on
+    /// it; the bytes sent are also seen by the receiver and can be read back.
+    pub fn new_half_duplex<const REMAP: u8>(
+        usart: USART1,
+        pin: TX,
+        config: Config,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Result<Self, ConfigError>
+    where
+        TX: HalfDuplex<REMAP>,
+    {
+        setup(&usart, REMAP, &config, pcfr, rcc, clocks)?;
+
+        usart.ctlr3.modify(|_, w| w.hdsel().set_bit());
+        usart
+            .ctlr1
+            .modify(|_, w| w.te().set_bit().re().set_bit().ue().set_bit());
+
+        Ok(Usart {
+            ck: NoCk {},
+            tx: pin,
+            rx: NoRx {},
+            cts: NoCts {},
+            rts: NoRts {},
+            usart,
+        })
     }
 }
 
@@ -248,106 +408,797 @@ impl<CK, TX, RX, CTS, RTS> Usart<CK, TX, RX, CTS, RTS> {
         self.ck = clock;
     }
 
-    pub fn write_u16(&mut self, word: u16) -> nb::Result<(), Infallible> {
-        if self.usart.statr.read().txe().bit_is_set() {
-            self.usart.datar.write(|w| w.dr().variant(word));
-            Ok(())
-        } else {
-            Err(nb::Error::WouldBlock)
+    /// Split into transmit and receive halves, e.g. to receive from an interrupt handler
+    ///
+    /// Each half only touches its own flags and DATAR direction. Use [`Self::join`] to get
+    /// the pins and the peripheral back.
+    pub fn split(self) -> (UsartTx, UsartRx, UsartPins<CK, TX, RX, CTS, RTS>) {
+        (
+            UsartTx { _0: () },
+            UsartRx { _0: () },
+            UsartPins {
+                usart: self.usart,
+                ck: self.ck,
+                tx: self.tx,
+                rx: self.rx,
+                cts: self.cts,
+                rts: self.rts,
+            },
+        )
+    }
+
+    /// Put the halves returned by [`Self::split`] back together
+    pub fn join(_tx: UsartTx, _rx: UsartRx, pins: UsartPins<CK, TX, RX, CTS, RTS>) -> Self {
+        Self {
+            usart: pins.usart,
+            ck: pins.ck,
+            tx: pins.tx,
+            rx: pins.rx,
+            cts: pins.cts,
+            rts: pins.rts,
         }
     }
 
+    /// Send a byte, blocking until there is room in the transmit register
+    pub fn write_byte(&mut self, byte: u8) {
+        nb::block!(self.write_u16(byte as u16)).ok();
+    }
+
+    /// Receive a byte, blocking until one is available
+    pub fn read_byte(&mut self) -> Result<u8, Error> {
+        nb::block!(self.read_u16()).map(|word| word as u8)
+    }
+
+    pub fn write_u16(&mut self, word: u16) -> nb::Result<(), Infallible> {
+        write_u16(&self.usart, word)
+    }
+
     pub fn flush(&mut self) -> nb::Result<(), Infallible> {
-        if self.usart.statr.read().tc().bit_is_set() {
-            Ok(())
-        } else {
-            Err(nb::Error::WouldBlock)
+        flush(&self.usart)
+    }
+
+    pub fn read_u16(&mut self) -> nb::Result<u16, Error> {
+        read_u16(&self.usart)
+    }
+}
+
+impl<CK, TX, RX> Usart<CK, TX, RX, NoCts, NoRts> {
+    /// Enable hardware flow control with the given CTS and RTS pins
+    ///
+    /// Pass [`NoCts`] or [`NoRts`] to only use one direction. The pins have to use the same
+    /// remap as TX and RX; with CTS the transmitter waits for the input to be low before each
+    /// frame, with RTS the output goes high while the receive register is full.
+    pub fn with_flow_control<const REMAP: u8, NEWCTS, NEWRTS>(
+        self,
+        cts: NEWCTS,
+        rts: NEWRTS,
+    ) -> Usart<CK, TX, RX, NEWCTS, NEWRTS>
+    where
+        TX: Tx<REMAP>,
+        RX: Rx<REMAP>,
+        NEWCTS: Cts<REMAP>,
+        NEWRTS: Rts<REMAP>,
+    {
+        NEWCTS::enable(&self.usart);
+        NEWRTS::enable(&self.usart);
+
+        Usart {
+            usart: self.usart,
+            ck: self.ck,
+            tx: self.tx,
+            rx: self.rx,
+            cts,
+            rts,
         }
     }
+}
+
+/// Length of the LIN break detected by the receiver
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LinBreakLength {
+    Bits10,
+    Bits11,
+}
+
+impl<TX, RX> Usart<NoCk, TX, RX, NoCts, NoRts> {
+    /// Switch to LIN mode, detecting breaks of `length` on the receiver
+    ///
+    /// LIN frames have a single stop bit, the stop bits are set to 1 by this. Half duplex
+    /// mode (HDSEL) is turned off as well, a USART from [`Usart::new_half_duplex`] has no
+    /// receiver afterwards. Clock output, smartcard and IrDA modes have to stay disabled,
+    /// they are at reset.
+    pub fn enable_lin(&mut self, length: LinBreakLength) {
+        self.usart.ctlr1.modify(|_, w| w.ue().clear_bit());
+        self.usart.ctlr3.modify(|_, w| w.hdsel().clear_bit());
+        self.usart.ctlr2.modify(|_, w| {
+            w.stop()
+                .variant(StopBits::STOP1.to_raw())
+                .lbdl()
+                .bit(length == LinBreakLength::Bits11)
+                .linen()
+                .set_bit()
+        });
+        self.usart.ctlr1.modify(|_, w| w.ue().set_bit());
+    }
+
+    /// Leave LIN mode
+    pub fn disable_lin(&mut self) {
+        self.usart.ctlr1.modify(|_, w| w.ue().clear_bit());
+        self.usart.ctlr2.modify(|_, w| w.linen().clear_bit());
+        self.usart.ctlr1.modify(|_, w| w.ue().set_bit());
+    }
+}
+
+impl<CK, TX, RX, CTS, RTS> Usart<CK, TX, RX, CTS, RTS> {
+    /// Baud rate generated by the current BRR value
+    pub fn baudrate(&self, clocks: &Clocks) -> u32 {
+        let div = self.usart.brr.read().bits().max(1);
+        USART1::clock(clocks).raw() / div
+    }
+
+    /// Send a break after the current frame, 13 low bits in LIN mode
+    ///
+    /// SBK is cleared by the hardware during the stop bit of the break.
+    pub fn send_break(&mut self) {
+        self.usart.ctlr1.modify(|_, w| w.sbk().set_bit());
+    }
+
+    /// Enable the LIN break detection interrupt (LBDIE)
+    pub fn listen_break(&mut self) {
+        self.usart.ctlr2.modify(|_, w| w.lbdie().set_bit());
+    }
+
+    /// Disable the LIN break detection interrupt
+    pub fn unlisten_break(&mut self) {
+        self.usart.ctlr2.modify(|_, w| w.lbdie().clear_bit());
+    }
+
+    /// Whether a LIN break has been detected (LBD)
+    pub fn is_break_detected(&self) -> bool {
+        self.usart.statr.read().lbd().bit_is_set()
+    }
+
+    /// Clear the LIN break detection flag
+    pub fn clear_break_detected(&mut self) {
+        // Flags of STATR are cleared by writing 0, writing 1 leaves them unchanged
+        self.usart.statr.write(|w| unsafe { w.bits(!(1 << 8)) });
+    }
+}
+
+/// Peripheral and pins of a [`Usart`] while it is split, see [`Usart::split`]
+pub struct UsartPins<CK, TX, RX, CTS, RTS> {
+    usart: USART1,
+    ck: CK,
+    tx: TX,
+    rx: RX,
+    cts: CTS,
+    rts: RTS,
+}
+
+/// Transmit half of a [`Usart`]
+pub struct UsartTx {
+    _0: (),
+}
+
+impl UsartTx {
+    #[inline(always)]
+    fn usart(&self) -> &usart1::RegisterBlock {
+        // NOTE(unsafe) the transmit half only writes DATAR and reads the transmit flags
+        unsafe { &*USART1::ptr() }
+    }
+
+    /// Send a byte, blocking until there is room in the transmit register
+    pub fn write_byte(&mut self, byte: u8) {
+        nb::block!(self.write_u16(byte as u16)).ok();
+    }
+
+    pub fn write_u16(&mut self, word: u16) -> nb::Result<(), Infallible> {
+        write_u16(self.usart(), word)
+    }
+
+    pub fn flush(&mut self) -> nb::Result<(), Infallible> {
+        flush(self.usart())
+    }
+}
+
+/// Receive half of a [`Usart`]
+pub struct UsartRx {
+    _0: (),
+}
+
+impl UsartRx {
+    #[inline(always)]
+    fn usart(&self) -> &usart1::RegisterBlock {
+        // NOTE(unsafe) the receive half only reads DATAR and the receive flags
+        unsafe { &*USART1::ptr() }
+    }
+
+    /// Receive a byte, blocking until one is available
+    pub fn read_byte(&mut self) -> Result<u8, Error> {
+        nb::block!(self.read_u16()).map(|word| word as u8)
+    }
 
     pub fn read_u16(&mut self) -> nb::Result<u16, Error> {
-        let statr = self.usart.statr.read();
+        read_u16(self.usart())
+    }
 
-        // Check for any errors
-        let err = if statr.pe().bit_is_set() {
-            Some(Error::Parity)
-        } else if statr.fe().bit_is_set() {
-            Some(Error::Framing)
-        } else if statr.ne().bit_is_set() {
-            Some(Error::Noise)
-        } else if statr.ore().bit_is_set() {
-            Some(Error::Overrun)
-        } else {
-            None
-        };
-
-        if let Some(err) = err {
-            // Some error occurred. In order to clear that error flag, you have to
-            // do a read from the statr register followed by a read from the datar register.
-            let _ = self.usart.statr.read();
-            let _ = self.usart.datar.read();
-            Err(nb::Error::Other(err))
-        } else {
-            // Check if a byte is available
-            if statr.rxne().bit_is_set() {
-                // Read the received byte
-                Ok(self.usart.datar.read().dr().bits())
-            } else {
-                Err(nb::Error::WouldBlock)
+    /// Enable the receive interrupt (RXNEIE), also raised on overrun errors
+    pub fn listen(&mut self) {
+        // CTLR1 is shared with the transmit half
+        critical_section::with(|_| self.usart().ctlr1.modify(|_, w| w.rxneie().set_bit()));
+    }
+
+    /// Disable the receive interrupt
+    pub fn unlisten(&mut self) {
+        critical_section::with(|_| self.usart().ctlr1.modify(|_, w| w.rxneie().clear_bit()));
+    }
+
+    /// Move the received bytes into `buffer`, to be called from the `USART1` interrupt handler
+    ///
+    /// DATAR is always read so the interrupt is acknowledged: a byte received while `buffer`
+    /// is full is counted in [`RingBuffer::dropped`], and a byte with an error (including an
+    /// overrun) is discarded. The first error is returned once all bytes have been handled.
+    pub fn on_interrupt<const N: usize>(
+        &mut self,
+        buffer: &mut RingBuffer<N>,
+    ) -> Result<(), Error> {
+        let mut result = Ok(());
+        loop {
+            match self.read_u16() {
+                Ok(word) => buffer.push(word as u8),
+                Err(nb::Error::WouldBlock) => return result,
+                Err(nb::Error::Other(err)) => {
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                }
             }
         }
     }
 }
 
-impl<CK, TX, RX, CTS, RTS> core::fmt::Write for Usart<CK, TX, RX, CTS, RTS>
-where
-    CK: 'static,
-    TX: 'static,
-    RX: 'static,
-    CTS: 'static,
-    RTS: 'static,
-{
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        (self as &mut dyn embedded_hal_02::serial::Write<u8, Error = _>).write_str(s)
+/// Fixed size byte queue filled by [`UsartRx::on_interrupt`]
+///
+/// Share it with the interrupt handler through a `critical_section::Mutex<RefCell<_>>`.
+pub struct RingBuffer<const N: usize> {
+    buffer: [u8; N],
+    start: usize,
+    len: usize,
+    dropped: usize,
+}
+
+impl<const N: usize> RingBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            start: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Append a byte, dropping it if the buffer is full
+    pub fn push(&mut self, byte: u8) {
+        if self.len == N {
+            self.dropped = self.dropped.wrapping_add(1);
+        } else {
+            self.buffer[(self.start + self.len) % N] = byte;
+            self.len += 1;
+        }
+    }
+
+    /// Take the oldest byte, `None` if the buffer is empty
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buffer[self.start];
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Number of bytes dropped because the buffer was full
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Transmit half of a DMA transfer, see [`UsartTx::write_dma`]
+pub struct UsartTxDma {
+    tx: UsartTx,
+    channel: dma::C4,
+    empty: bool,
+}
+
+/// Receive half of a DMA transfer, see [`UsartRx::read_dma`]
+pub struct UsartRxDma {
+    rx: UsartRx,
+    channel: dma::C5,
+    len: usize,
+    until_idle: bool,
+    received: usize,
+}
+
+impl UsartTx {
+    /// Send `buffer` in the background with DMA channel 4
+    ///
+    /// [`Transfer::wait`] returns once the last byte has left the shift register. An empty
+    /// `buffer` completes right away, without starting the channel.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 bytes.
+    pub fn write_dma(
+        self,
+        mut channel: dma::C4,
+        buffer: &'static [u8],
+    ) -> Transfer<&'static [u8], UsartTxDma> {
+        channel.stop();
+        channel.configure(
+            dma::Direction::MemoryToPeripheral,
+            dma::WordSize::Bits8,
+            false,
+        );
+        channel.set_peripheral_address(self.usart().datar.as_ptr() as u32, false);
+        channel.set_memory_address(buffer.as_ptr() as u32, true);
+        channel.set_transfer_length(buffer.len());
+
+        // A DMA transfer of length 0 would never complete
+        let empty = buffer.is_empty();
+        if !empty {
+            // TC is set after reset and DMA writes to DATAR don't clear it, so flush() in
+            // finish() would return before the last byte is out
+            // NOTE(unsafe) rc_w0 flags, writing 1 leaves the others unchanged
+            self.usart().statr.write(|w| unsafe { w.bits(!(1 << 6)) });
+            critical_section::with(|_| self.usart().ctlr3.modify(|_, w| w.dmat().set_bit()));
+            channel.start();
+        }
+
+        Transfer::new(
+            buffer,
+            UsartTxDma {
+                tx: self,
+                channel,
+                empty,
+            },
+        )
     }
 }
 
-impl<CK, TX, RX, CTS, RTS> Write<u8> for Usart<CK, TX, RX, CTS, RTS> {
-    type Error = Infallible;
+impl UsartRx {
+    /// Fill `buffer` in the background with DMA channel 5
+    ///
+    /// An empty `buffer` completes right away, without starting the channel.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 bytes.
+    pub fn read_dma(
+        self,
+        channel: dma::C5,
+        buffer: &'static mut [u8],
+    ) -> Transfer<&'static mut [u8], UsartRxDma> {
+        self.start_dma(channel, buffer, false)
+    }
 
-    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
-        self.write_u16(word as u16)
+    /// Like [`Self::read_dma`], but the transfer also ends when the line goes idle for one
+    /// frame after a byte was received, to receive frames of unknown length
+    ///
+    /// [`UsartRxDma::received`] tells how much of `buffer` was filled.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 bytes.
+    pub fn read_dma_until_idle(
+        self,
+        channel: dma::C5,
+        buffer: &'static mut [u8],
+    ) -> Transfer<&'static mut [u8], UsartRxDma> {
+        self.start_dma(channel, buffer, true)
     }
 
-    fn flush(&mut self) -> nb::Result<(), Self::Error> {
-        self.flush()
+    fn start_dma(
+        self,
+        mut channel: dma::C5,
+        buffer: &'static mut [u8],
+        until_idle: bool,
+    ) -> Transfer<&'static mut [u8], UsartRxDma> {
+        channel.stop();
+        channel.configure(
+            dma::Direction::PeripheralToMemory,
+            dma::WordSize::Bits8,
+            false,
+        );
+        channel.set_peripheral_address(self.usart().datar.as_ptr() as u32, false);
+        channel.set_memory_address(buffer.as_mut_ptr() as u32, true);
+        channel.set_transfer_length(buffer.len());
+
+        let len = buffer.len();
+        // A DMA transfer of length 0 would never complete
+        if len != 0 {
+            // A stale IDLE flag is cleared by reading STATR then DATAR
+            let _ = self.usart().statr.read();
+            let _ = self.usart().datar.read();
+
+            channel.start();
+            critical_section::with(|_| self.usart().ctlr3.modify(|_, w| w.dmar().set_bit()));
+        }
+
+        Transfer::new(
+            buffer,
+            UsartRxDma {
+                rx: self,
+                channel,
+                len,
+                until_idle,
+                received: 0,
+            },
+        )
     }
 }
 
-impl<CK, TX, RX, CTS, RTS> Write<u16> for Usart<CK, TX, RX, CTS, RTS> {
-    type Error = Infallible;
+impl UsartTxDma {
+    /// Return the transmit half and the DMA channel once the transfer is over
+    pub fn release(self) -> (UsartTx, dma::C4) {
+        (self.tx, self.channel)
+    }
+}
 
-    fn write(&mut self, word: u16) -> nb::Result<(), Self::Error> {
-        self.write_u16(word)
+impl UsartRxDma {
+    /// Number of bytes written to the buffer, once the transfer is over
+    pub fn received(&self) -> usize {
+        self.received
     }
 
-    fn flush(&mut self) -> nb::Result<(), Self::Error> {
-        self.flush()
+    /// Return the receive half and the DMA channel once the transfer is over
+    pub fn release(self) -> (UsartRx, dma::C5) {
+        (self.rx, self.channel)
     }
 }
 
-impl<CK, TX, RX, CTS, RTS> Read<u8> for Usart<CK, TX, RX, CTS, RTS> {
+impl TransferPayload for UsartTxDma {
     type Error = Error;
 
-    fn read(&mut self) -> nb::Result<u8, Self::Error> {
-        self.read_u16().map(|word16| word16 as u8)
+    fn is_done(&self) -> bool {
+        self.empty || self.channel.is_complete() || self.channel.has_error()
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let failed = self.channel.has_error();
+        self.channel.stop();
+
+        let usart = self.tx.usart();
+        critical_section::with(|_| usart.ctlr3.modify(|_, w| w.dmat().clear_bit()));
+        if failed {
+            return Err(Error::Dma);
+        }
+
+        nb::block!(flush(usart)).ok();
+        Ok(())
     }
 }
 
-impl<CK, TX, RX, CTS, RTS> Read<u16> for Usart<CK, TX, RX, CTS, RTS> {
+impl TransferPayload for UsartRxDma {
     type Error = Error;
 
-    fn read(&mut self) -> nb::Result<u16, Self::Error> {
-        self.read_u16()
+    fn is_done(&self) -> bool {
+        self.len == 0
+            || self.channel.is_complete()
+            || self.channel.has_error()
+            || (self.until_idle && statr().idle().bit_is_set())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let failed = self.channel.has_error();
+        self.channel.stop();
+        if self.len == 0 {
+            return Ok(());
+        }
+        self.received = self.len - self.channel.remaining() as usize;
+
+        let usart = self.rx.usart();
+        critical_section::with(|_| usart.ctlr3.modify(|_, w| w.dmar().clear_bit()));
+        if failed {
+            return Err(Error::Dma);
+        }
+
+        // Reading DATAR after STATR clears the error and IDLE flags
+        let statr = usart.statr.read();
+        let _ = usart.datar.read();
+        if statr.pe().bit_is_set() {
+            Err(Error::Parity)
+        } else if statr.fe().bit_is_set() {
+            Err(Error::Framing)
+        } else if statr.ne().bit_is_set() {
+            Err(Error::Noise)
+        } else if statr.ore().bit_is_set() {
+            Err(Error::Overrun)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn write_u16(usart: &usart1::RegisterBlock, word: u16) -> nb::Result<(), Infallible> {
+    if usart.statr.read().txe().bit_is_set() {
+        usart.datar.write(|w| w.dr().variant(word));
+        Ok(())
+    } else {
+        Err(nb::Error::WouldBlock)
+    }
+}
+
+fn flush(usart: &usart1::RegisterBlock) -> nb::Result<(), Infallible> {
+    if usart.statr.read().tc().bit_is_set() {
+        Ok(())
+    } else {
+        Err(nb::Error::WouldBlock)
+    }
+}
+
+fn read_u16(usart: &usart1::RegisterBlock) -> nb::Result<u16, Error> {
+    let statr = usart.statr.read();
+
+    // Check for any errors
+    let err = if statr.pe().bit_is_set() {
+        Some(Error::Parity)
+    } else if statr.fe().bit_is_set() {
+        Some(Error::Framing)
+    } else if statr.ne().bit_is_set() {
+        Some(Error::Noise)
+    } else if statr.ore().bit_is_set() {
+        Some(Error::Overrun)
+    } else {
+        None
+    };
+
+    if let Some(err) = err {
+        // Some error occurred. In order to clear that error flag, you have to
+        // do a read from the statr register followed by a read from the datar register.
+        let _ = usart.statr.read();
+        let _ = usart.datar.read();
+        Err(nb::Error::Other(err))
+    } else {
+        // Check if a byte is available
+        if statr.rxne().bit_is_set() {
+            // Read the received byte
+            Ok(usart.datar.read().dr().bits())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+/// Only wait on an infallible transmit operation, for traits sharing the receive error type
+fn never_fails<T>(result: nb::Result<T, Infallible>) -> nb::Result<T, Error> {
+    result.map_err(|err| match err {
+        nb::Error::WouldBlock => nb::Error::WouldBlock,
+        nb::Error::Other(never) => match never {},
+    })
+}
+
+impl embedded_hal_nb::serial::Error for Error {
+    fn kind(&self) -> embedded_hal_nb::serial::ErrorKind {
+        use embedded_hal_nb::serial::ErrorKind;
+        match self {
+            Error::Framing => ErrorKind::FrameFormat,
+            Error::Noise => ErrorKind::Noise,
+            Error::Overrun => ErrorKind::Overrun,
+            Error::Parity => ErrorKind::Parity,
+            Error::Dma => ErrorKind::Other,
+        }
+    }
+}
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
     }
 }
+
+macro_rules! serial_error_type {
+    ($([$($g:ident),*] $ty:ty,)+) => {
+        $(
+            impl<$($g),*> embedded_hal_nb::serial::ErrorType for $ty {
+                type Error = Error;
+            }
+
+            impl<$($g),*> embedded_io::ErrorType for $ty {
+                type Error = Error;
+            }
+        )+
+    };
+}
+
+macro_rules! serial_write {
+    ($([$($g:ident),*] $ty:ty,)+) => {
+        $(
+            impl<$($g),*> core::fmt::Write for $ty {
+                fn write_str(&mut self, s: &str) -> fmt::Result {
+                    for byte in s.bytes() {
+                        self.write_byte(byte);
+                    }
+                    Ok(())
+                }
+            }
+
+            impl<$($g),*> Write<u8> for $ty {
+                type Error = Infallible;
+
+                fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+                    self.write_u16(word as u16)
+                }
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    self.flush()
+                }
+            }
+
+            impl<$($g),*> Write<u16> for $ty {
+                type Error = Infallible;
+
+                fn write(&mut self, word: u16) -> nb::Result<(), Self::Error> {
+                    self.write_u16(word)
+                }
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    self.flush()
+                }
+            }
+
+            impl<$($g),*> embedded_hal_nb::serial::Write<u8> for $ty {
+                fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+                    never_fails(self.write_u16(word as u16))
+                }
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    never_fails(<$ty>::flush(self))
+                }
+            }
+
+            impl<$($g),*> embedded_hal_nb::serial::Write<u16> for $ty {
+                fn write(&mut self, word: u16) -> nb::Result<(), Self::Error> {
+                    never_fails(self.write_u16(word))
+                }
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    never_fails(<$ty>::flush(self))
+                }
+            }
+
+            impl<$($g),*> embedded_io::Write for $ty {
+                /// Block until the first byte is queued, then queue the others while there is room
+                fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+                    let Some((&first, rest)) = buf.split_first() else {
+                        return Ok(0);
+                    };
+                    self.write_byte(first);
+
+                    let mut count = 1;
+                    for &byte in rest {
+                        if self.write_u16(byte as u16).is_err() {
+                            break;
+                        }
+                        count += 1;
+                    }
+                    Ok(count)
+                }
+
+                /// Block until the last byte has left the shift register (TC)
+                fn flush(&mut self) -> Result<(), Self::Error> {
+                    nb::block!(<$ty>::flush(self)).ok();
+                    Ok(())
+                }
+            }
+
+            impl<$($g),*> embedded_io::WriteReady for $ty {
+                fn write_ready(&mut self) -> Result<bool, Self::Error> {
+                    Ok(statr().txe().bit_is_set())
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! serial_read {
+    ($([$($g:ident),*] $ty:ty,)+) => {
+        $(
+            impl<$($g),*> Read<u8> for $ty {
+                type Error = Error;
+
+                fn read(&mut self) -> nb::Result<u8, Self::Error> {
+                    self.read_u16().map(|word16| word16 as u8)
+                }
+            }
+
+            impl<$($g),*> Read<u16> for $ty {
+                type Error = Error;
+
+                fn read(&mut self) -> nb::Result<u16, Self::Error> {
+                    self.read_u16()
+                }
+            }
+
+            impl<$($g),*> embedded_hal_nb::serial::Read<u8> for $ty {
+                fn read(&mut self) -> nb::Result<u8, Self::Error> {
+                    self.read_u16().map(|word16| word16 as u8)
+                }
+            }
+
+            impl<$($g),*> embedded_hal_nb::serial::Read<u16> for $ty {
+                fn read(&mut self) -> nb::Result<u16, Self::Error> {
+                    self.read_u16()
+                }
+            }
+
+            impl<$($g),*> embedded_io::Read for $ty {
+                /// Block until a byte is received, then keep reading the bytes already available
+                fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+                    let Some((first, rest)) = buf.split_first_mut() else {
+                        return Ok(0);
+                    };
+                    *first = self.read_byte()?;
+
+                    let mut count = 1;
+                    for byte in rest {
+                        match self.read_u16() {
+                            Ok(word) => *byte = word as u8,
+                            Err(nb::Error::WouldBlock) => break,
+                            Err(nb::Error::Other(err)) => return Err(err),
+                        }
+                        count += 1;
+                    }
+                    Ok(count)
+                }
+            }
+
+            impl<$($g),*> embedded_io::ReadReady for $ty {
+                fn read_ready(&mut self) -> Result<bool, Self::Error> {
+                    Ok(statr().rxne().bit_is_set())
+                }
+            }
+        )+
+    };
+}
+
+/// Status flags, for the readiness checks that don't borrow the registers
+#[inline(always)]
+fn statr() -> usart1::statr::R {
+    // NOTE(unsafe) atomic read with no side effects
+    unsafe { (*USART1::ptr()).statr.read() }
+}
+
+serial_error_type! {
+    [CK, TX, RX, CTS, RTS] Usart<CK, TX, RX, CTS, RTS>,
+    [] UsartTx,
+    [] UsartRx,
+}
+
+serial_write! {
+    [CK, TX, RX, CTS, RTS] Usart<CK, TX, RX, CTS, RTS>,
+    [] UsartTx,
+}
+
+serial_read! {
+    [CK, TX, RX, CTS, RTS] Usart<CK, TX, RX, CTS, RTS>,
+    [] UsartRx,
+}
@@ -0,0 +1,1014 @@
+//! Serial Peripheral Interface (SPI)
+//!
+//! SPI1 has a single set of SCK (PC5), MISO (PC7) and MOSI (PC6) pins, the remap option
+//! (SPI1_RM) only moves the hardware NSS output from PC1 to PC0. Chip select is usually
+//! driven from any GPIO instead, which works for several devices on the same bus.
+//! In bidirectional mode ([`Spi::new_bidi`]) MOSI is the only data line, for 3-wire devices.
+//! [`SpiSlave`] responds to an external master instead.
+//!
+//! [`Spi`] implements the embedded-hal 1.0 `SpiBus`. Drivers expecting an `SpiDevice` take
+//! it paired with its chip select pin, e.g. with `ExclusiveDevice` from `embedded-hal-bus`:
+//!
+//! ```ignore
+//! let cs = gpioc.pc3.into_push_pull_output();
+//! let device = ExclusiveDevice::new(spi, cs, delay)?;
+//! ```
+
+use core::marker::PhantomData;
+
+use fugit::HertzU32;
+
+pub use embedded_hal_1::spi::{Mode, Phase, Polarity, MODE_0, MODE_1, MODE_2, MODE_3};
+
+use crate::{
+    afio::PCFR,
+    dma::{self, Transfer, TransferPayload},
+    gpio::*,
+    pac::SPI1,
+    rcc::{BusClock, Clocks, Enable, Rcc, Reset},
+};
+
+/// SPI error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A received byte was lost because the previous one hadn't been read yet
+    Overrun,
+    /// NSS was pulled low by another master
+    ModeFault,
+    /// The received CRC didn't match
+    Crc,
+    /// Bus error during a DMA transfer
+    Dma,
+}
+
+/// Reasons an [`SpiConfig`] can't be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiConfigError {
+    /// The frequency is zero, or below the peripheral clock divided by 256
+    UnachievableFrequency,
+    /// [`NssMode::HardwareOutput`] needs the NSS pin in the pin tuple, [`NssMode::Software`]
+    /// doesn't use it
+    NssMismatch,
+}
+
+/// Management of the NSS (slave select) signal in master mode
+///
+/// In master mode the peripheral watches its NSS input for another master taking the bus.
+/// If NSS is neither managed in software nor driven as an output, a floating or low NSS
+/// pin makes the peripheral drop out of master mode (mode fault, MSTR and SPE are cleared)
+/// and no clock is generated anymore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NssMode {
+    /// The internal NSS is held high (SSM and SSI), chip select is driven from a GPIO
+    ///
+    /// This keeps CS low across any number of transfers, which most devices need.
+    Software,
+    /// NSS is driven low by the peripheral while it is enabled (SSOE)
+    ///
+    /// Only suits a single device on the bus that accepts CS staying low between frames.
+    HardwareOutput,
+}
+
+/// SPI peripheral configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpiConfig {
+    pub mode: Mode,
+    /// SCK frequency, the highest achievable one not above it is used
+    pub frequency: HertzU32,
+    pub nss: NssMode,
+}
+
+impl SpiConfig {
+    /// Software managed NSS with the given mode and frequency
+    #[inline]
+    pub const fn new(mode: Mode, frequency: HertzU32) -> Self {
+        Self {
+            mode,
+            frequency,
+            nss: NssMode::Software,
+        }
+    }
+
+    /// Select how NSS is managed
+    #[inline]
+    pub const fn nss(mut self, nss: NssMode) -> Self {
+        self.nss = nss;
+        self
+    }
+}
+
+/// Word of a data frame, `u8` for 8 bit frames and `u16` for 16 bit frames (DFF)
+pub trait DataSize: Copy + Default + crate::Sealed + 'static {
+    /// Value of the DFF bit
+    const DFF: bool;
+    /// Item size of the DMA transfers
+    const DMA_SIZE: dma::WordSize;
+
+    fn from_frame(frame: u16) -> Self;
+    fn into_frame(self) -> u16;
+}
+
+impl crate::Sealed for u8 {}
+impl DataSize for u8 {
+    const DFF: bool = false;
+    const DMA_SIZE: dma::WordSize = dma::WordSize::Bits8;
+
+    fn from_frame(frame: u16) -> Self {
+        frame as u8
+    }
+
+    fn into_frame(self) -> u16 {
+        self as u16
+    }
+}
+
+impl crate::Sealed for u16 {}
+impl DataSize for u16 {
+    const DFF: bool = true;
+    const DMA_SIZE: dma::WordSize = dma::WordSize::Bits16;
+
+    fn from_frame(frame: u16) -> Self {
+        frame
+    }
+
+    fn into_frame(self) -> u16 {
+        self
+    }
+}
+
+/// Ready to use SPI peripheral, in master mode, exchanging words of type `W`
+///
+/// The peripheral starts with 8 bit frames, see [`Spi::into_data_size`].
+pub struct Spi<PINS, W = u8> {
+    spi: SPI1,
+    pins: PINS,
+    _word: PhantomData<W>,
+}
+
+impl<PINS: SPI1Pins> Spi<PINS> {
+    /// Initialise SPI1 as a master
+    ///
+    /// # Panics
+    ///
+    /// If `config` can't be used with these pins and clocks, see [`Self::try_spi1`]
+    pub fn spi1(
+        spi: SPI1,
+        pins: PINS,
+        config: SpiConfig,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Self {
+        Self::try_spi1(spi, pins, config, pcfr, rcc, clocks).expect("invalid SPI configuration")
+    }
+
+    /// Initialise SPI1 as a master, checking the configuration before touching the peripheral
+    ///
+    /// The SCK frequency is PCLK / 2^(BR + 1), the highest one not above the requested
+    /// frequency is used. SCK and MOSI are switched to 50 MHz pads if they were slowed down.
+    pub fn try_spi1(
+        spi: SPI1,
+        mut pins: PINS,
+        config: SpiConfig,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Result<Self, SpiConfigError> {
+        setup(&spi, &config, PINS::REMAP, PINS::NSS, pcfr, rcc, clocks)?;
+        pins.require_speed();
+        spi.ctlr1.modify(|_, w| w.spe().set_bit());
+
+        Ok(Self {
+            spi,
+            pins,
+            _word: PhantomData,
+        })
+    }
+}
+
+impl Spi<BidiPins> {
+    /// Initialise SPI1 as a master in bidirectional mode, with a single data line on MOSI
+    ///
+    /// # Panics
+    ///
+    /// If `config` can't be used with these pins and clocks, see [`Self::try_new_bidi`]
+    pub fn new_bidi(
+        spi: SPI1,
+        sck: PC5<Alternate<PushPull>>,
+        mosi: PC6<Alternate<PushPull>>,
+        config: SpiConfig,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Self {
+        Self::try_new_bidi(spi, sck, mosi, config, pcfr, rcc, clocks)
+            .expect("invalid SPI configuration")
+    }
+
+    /// Initialise SPI1 as a master in bidirectional mode, checking the configuration
+    /// before touching the peripheral
+    ///
+    /// Used by 3-wire devices sharing one data line for both directions. The line is
+    /// driven (BIDIOE) except during [`Spi::read`], and [`NssMode::HardwareOutput`] isn't
+    /// available as the NSS pin isn't used.
+    pub fn try_new_bidi(
+        spi: SPI1,
+        mut sck: PC5<Alternate<PushPull>>,
+        mut mosi: PC6<Alternate<PushPull>>,
+        config: SpiConfig,
+        pcfr: &mut PCFR,
+        rcc: &mut Rcc,
+        clocks: &Clocks,
+    ) -> Result<Self, SpiConfigError> {
+        setup(&spi, &config, false, false, pcfr, rcc, clocks)?;
+        sck.require_speed(Speed::Mhz50);
+        mosi.require_speed(Speed::Mhz50);
+        spi.ctlr1
+            .modify(|_, w| w.bidimode().set_bit().bidioe().set_bit());
+        spi.ctlr1.modify(|_, w| w.spe().set_bit());
+
+        Ok(Self {
+            spi,
+            pins: (sck, mosi),
+            _word: PhantomData,
+        })
+    }
+}
+
+impl<W: DataSize> Spi<BidiPins, W> {
+    /// Fill `words` from the data line, which is released beforehand
+    ///
+    /// In receive mode the clock runs as long as the peripheral is enabled, so it is
+    /// disabled during the last frame to receive exactly `words.len()` words. This runs in
+    /// a critical section to get that timing right, and words can still be lost with
+    /// [`Error::Overrun`] at the highest frequencies.
+    pub fn read(&mut self, words: &mut [W]) -> Result<(), Error> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.wait_idle();
+
+        // One SCK period is 2^(BR + 1) peripheral clock cycles, and each delay loop
+        // iteration takes at least one cycle
+        let sck_cycle = 2u32 << self.spi.ctlr1.read().br().bits();
+        let last = words.len() - 1;
+
+        let result = critical_section::with(|_| {
+            self.spi.ctlr1.modify(|_, w| w.spe().clear_bit());
+            self.spi.ctlr1.modify(|_, w| w.bidioe().clear_bit());
+
+            // Drop a stale received word
+            let _ = self.spi.datar.read();
+            let _ = self.spi.statr.read();
+
+            self.spi.ctlr1.modify(|_, w| w.spe().set_bit());
+            for (i, word) in words.iter_mut().enumerate() {
+                if i == last {
+                    // The last frame has started, stop the clock once it completes
+                    qingke::riscv::asm::delay(sck_cycle);
+                    self.spi.ctlr1.modify(|_, w| w.spe().clear_bit());
+                }
+                match nb::block!(self.read_word()) {
+                    Ok(w) => *word = w,
+                    Err(e) => {
+                        self.spi.ctlr1.modify(|_, w| w.spe().clear_bit());
+                        return Err(e);
+                    }
+                }
+            }
+            Ok(())
+        });
+
+        // Drive the line again, no clock is generated until a word is written
+        self.spi.ctlr1.modify(|_, w| w.bidioe().set_bit());
+        self.spi.ctlr1.modify(|_, w| w.spe().set_bit());
+        result
+    }
+}
+
+/// SPI1 in slave mode, clocked by an external master
+pub struct SpiSlave<PINS, W = u8> {
+    spi: Spi<PINS, W>,
+}
+
+impl<PINS: SPI1SlavePins> SpiSlave<PINS> {
+    /// Initialise SPI1 as a slave using `mode`, the same as the master's
+    ///
+    /// With the NSS pin the slave only takes part in transfers while the master pulls it
+    /// low, and MISO is only driven then. Without it the slave is always selected.
+    pub fn spi1(spi: SPI1, mut pins: PINS, mode: Mode, pcfr: &mut PCFR, rcc: &mut Rcc) -> Self {
+        SPI1::enable(&mut rcc.apb2);
+        SPI1::reset(&mut rcc.apb2);
+
+        pins.require_speed();
+
+        pcfr.set_spi1_remap(PINS::REMAP);
+
+        // A software NSS is held low (SSM, SSI cleared) to always select the slave
+        spi.ctlr2.reset();
+        spi.ctlr1.write(|w| {
+            w.cpha()
+                .bit(mode.phase == Phase::CaptureOnSecondTransition)
+                .cpol()
+                .bit(mode.polarity == Polarity::IdleHigh)
+                .mstr()
+                .clear_bit()
+                .lsbfirst()
+                .clear_bit()
+                .ssm()
+                .bit(!PINS::NSS)
+                .ssi()
+                .clear_bit()
+                .dff()
+                .clear_bit()
+        });
+        spi.ctlr1.modify(|_, w| w.spe().set_bit());
+
+        Self {
+            spi: Spi {
+                spi,
+                pins,
+                _word: PhantomData,
+            },
+        }
+    }
+}
+
+impl<PINS, W: DataSize> SpiSlave<PINS, W> {
+    /// Send `words` and replace each one with the word received while it was sent,
+    /// blocking until the master has clocked all of them
+    ///
+    /// The first word is loaded before the master starts the clock, and each following
+    /// one as soon as the previous frame has started, so the master can send back to back.
+    pub fn transfer(&mut self, words: &mut [W]) -> Result<(), Error> {
+        let Some(&first) = words.first() else {
+            return Ok(());
+        };
+        nb::block!(self.spi.send_word(first))?;
+
+        for i in 0..words.len() {
+            if let Some(&next) = words.get(i + 1) {
+                nb::block!(self.spi.send_word(next))?;
+            }
+            words[i] = nb::block!(self.spi.read_word())?;
+        }
+        Ok(())
+    }
+
+    /// Switch to frames of `NW` words, see [`Spi::into_data_size`]
+    pub fn into_data_size<NW: DataSize>(self) -> SpiSlave<PINS, NW> {
+        SpiSlave {
+            spi: self.spi.into_data_size(),
+        }
+    }
+
+    /// Disable the peripheral and return its raw hardware resources
+    ///
+    /// Unlike the master this doesn't wait for a loaded word to be clocked out.
+    pub fn release(self) -> (SPI1, PINS) {
+        let Spi { spi, pins, .. } = self.spi;
+        spi.ctlr1.modify(|_, w| w.spe().clear_bit());
+
+        (spi, pins)
+    }
+}
+
+/// Common initialisation, up to enabling the peripheral
+fn setup(
+    spi: &SPI1,
+    config: &SpiConfig,
+    remap: bool,
+    nss_pin: bool,
+    pcfr: &mut PCFR,
+    rcc: &mut Rcc,
+    clocks: &Clocks,
+) -> Result<(), SpiConfigError> {
+    let br = prescaler(SPI1::clock(clocks), config.frequency)?;
+    let hardware_nss = config.nss == NssMode::HardwareOutput;
+    if hardware_nss != nss_pin {
+        return Err(SpiConfigError::NssMismatch);
+    }
+    let mode = config.mode;
+
+    SPI1::enable(&mut rcc.apb2);
+    SPI1::reset(&mut rcc.apb2);
+
+    pcfr.set_spi1_remap(remap);
+
+    // With a software NSS held high (SSM and SSI) the peripheral never sees another
+    // master, with the hardware NSS output (SSOE) the pin is low while SPE is set
+    spi.ctlr2.write(|w| w.ssoe().bit(hardware_nss));
+    spi.ctlr1.write(|w| {
+        w.cpha()
+            .bit(mode.phase == Phase::CaptureOnSecondTransition)
+            .cpol()
+            .bit(mode.polarity == Polarity::IdleHigh)
+            .mstr()
+            .set_bit()
+            .br()
+            .variant(br)
+            .lsbfirst()
+            .clear_bit()
+            .ssm()
+            .bit(!hardware_nss)
+            .ssi()
+            .set_bit()
+            .dff()
+            .clear_bit()
+    });
+
+    Ok(())
+}
+
+impl<PINS, W: DataSize> Spi<PINS, W> {
+    /// Switch to frames of `NW` words, e.g. `spi.into_data_size::<u16>()` for 16 bit frames
+    ///
+    /// The peripheral is disabled while DFF is written, after the current frame went out.
+    pub fn into_data_size<NW: DataSize>(self) -> Spi<PINS, NW> {
+        self.wait_idle();
+        self.spi.ctlr1.modify(|_, w| w.spe().clear_bit());
+        self.spi.ctlr1.modify(|_, w| w.dff().bit(NW::DFF));
+        self.spi.ctlr1.modify(|_, w| w.spe().set_bit());
+
+        Spi {
+            spi: self.spi,
+            pins: self.pins,
+            _word: PhantomData,
+        }
+    }
+
+    /// Disable the peripheral and return its raw hardware resources
+    pub fn release(self) -> (SPI1, PINS) {
+        self.wait_idle();
+        self.spi.ctlr1.modify(|_, w| w.spe().clear_bit());
+
+        (self.spi, self.pins)
+    }
+}
+
+impl<PINS: SPI1Pins, W: DataSize> Spi<PINS, W> {
+    /// Send `words` and replace each one with the word received while it was sent
+    pub fn transfer(&mut self, words: &mut [W]) -> Result<(), Error> {
+        for word in words.iter_mut() {
+            *word = self.exchange(*word)?;
+        }
+        Ok(())
+    }
+}
+
+impl<PINS, W: DataSize> Spi<PINS, W> {
+    /// Send `words`, discarding the received words
+    ///
+    /// A new word is loaded as soon as the transmit buffer is empty, so the words go out
+    /// back to back. Returns once the last one has been shifted out.
+    pub fn write(&mut self, words: &[W]) -> Result<(), Error> {
+        for word in words {
+            while self.spi.statr.read().txe().bit_is_clear() {}
+            self.spi
+                .datar
+                .write(|w| w.datar().variant(word.into_frame()));
+        }
+        self.end_write()
+    }
+
+    /// Wait for the last word of a write to go out, then drop the received words
+    fn end_write(&mut self) -> Result<(), Error> {
+        self.wait_idle();
+
+        // The unread words have set OVR, which is cleared by reading DATAR then STATR
+        let _ = self.spi.datar.read();
+        let sr = self.spi.statr.read();
+        if sr.modf().bit_is_set() {
+            return Err(self.clear_mode_fault());
+        }
+        Ok(())
+    }
+
+    /// Send a single word and return the one received at the same time
+    fn exchange(&mut self, word: W) -> Result<W, Error> {
+        nb::block!(self.send_word(word))?;
+        nb::block!(self.read_word())
+    }
+
+    /// Load a word into the transmit buffer if it is empty
+    fn send_word(&mut self, word: W) -> nb::Result<(), Error> {
+        if self.spi.statr.read().txe().bit_is_set() {
+            self.spi
+                .datar
+                .write(|w| w.datar().variant(word.into_frame()));
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Take the received word, if any
+    fn read_word(&mut self) -> nb::Result<W, Error> {
+        let sr = self.spi.statr.read();
+        if sr.ovr().bit_is_set() {
+            // Reading DATAR then STATR clears the flag
+            let _ = self.spi.datar.read();
+            let _ = self.spi.statr.read();
+            Err(nb::Error::Other(Error::Overrun))
+        } else if sr.modf().bit_is_set() {
+            Err(nb::Error::Other(self.clear_mode_fault()))
+        } else if sr.crcerr().bit_is_set() {
+            self.spi.statr.modify(|_, w| w.crcerr().clear_bit());
+            Err(nb::Error::Other(Error::Crc))
+        } else if sr.rxne().bit_is_set() {
+            Ok(W::from_frame(self.spi.datar.read().bits() as u16))
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// A mode fault clears MSTR and SPE, writing CTLR1 after reading STATR clears the flag
+    fn clear_mode_fault(&mut self) -> Error {
+        self.spi
+            .ctlr1
+            .modify(|_, w| w.mstr().set_bit().spe().set_bit());
+        Error::ModeFault
+    }
+
+    /// Wait for the current transfer to leave the shift register
+    fn wait_idle(&self) {
+        while self.spi.statr.read().txe().bit_is_clear() {}
+        while self.spi.statr.read().bsy().bit_is_set() {}
+    }
+}
+
+/// Transmit only DMA transfer, see [`Spi::write_dma`]
+pub struct SpiTxDma<PINS, W = u8> {
+    spi: Spi<PINS, W>,
+    channel: dma::C3,
+    empty: bool,
+}
+
+/// Full duplex DMA transfer, see [`Spi::transfer_dma`]
+pub struct SpiTxRxDma<PINS, W = u8> {
+    spi: Spi<PINS, W>,
+    rx_channel: dma::C2,
+    tx_channel: dma::C3,
+    empty: bool,
+}
+
+impl<PINS, W: DataSize> Spi<PINS, W> {
+    /// Send `buffer` in the background with DMA channel 3, discarding the received words
+    ///
+    /// [`Transfer::wait`] returns once the last word has been shifted out. An empty `buffer`
+    /// completes right away, without starting the channel.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 words.
+    pub fn write_dma(
+        self,
+        mut channel: dma::C3,
+        buffer: &'static [W],
+    ) -> Transfer<&'static [W], SpiTxDma<PINS, W>> {
+        channel.stop();
+        channel.configure(dma::Direction::MemoryToPeripheral, W::DMA_SIZE, false);
+        channel.set_peripheral_address(self.spi.datar.as_ptr() as u32, false);
+        channel.set_memory_address(buffer.as_ptr() as u32, true);
+        channel.set_transfer_length(buffer.len());
+
+        // A DMA transfer of length 0 would never complete
+        let empty = buffer.is_empty();
+        if !empty {
+            channel.start();
+            self.spi.ctlr2.modify(|_, w| w.txdmaen().set_bit());
+        }
+
+        Transfer::new(
+            buffer,
+            SpiTxDma {
+                spi: self,
+                channel,
+                empty,
+            },
+        )
+    }
+}
+
+impl<PINS: SPI1Pins, W: DataSize> Spi<PINS, W> {
+    /// Send `buffer` in the background and replace it with the received words, using DMA
+    /// channel 2 to receive and channel 3 to transmit
+    ///
+    /// The receive channel is started first, so it is ready for the first byte before the
+    /// transmit requests start the clock. Each word is sent before the one received in its
+    /// place is written back, so the buffer can be shared by both channels. An empty `buffer`
+    /// completes right away, without starting the channels.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is longer than 65535 words.
+    pub fn transfer_dma(
+        self,
+        mut rx_channel: dma::C2,
+        mut tx_channel: dma::C3,
+        buffer: &'static mut [W],
+    ) -> Transfer<&'static mut [W], SpiTxRxDma<PINS, W>> {
+        let address = self.spi.datar.as_ptr() as u32;
+        let (ptr, len) = (buffer.as_mut_ptr() as u32, buffer.len());
+
+        rx_channel.stop();
+        rx_channel.configure(dma::Direction::PeripheralToMemory, W::DMA_SIZE, false);
+        rx_channel.set_peripheral_address(address, false);
+        rx_channel.set_memory_address(ptr, true);
+        rx_channel.set_transfer_length(len);
+
+        tx_channel.stop();
+        tx_channel.configure(dma::Direction::MemoryToPeripheral, W::DMA_SIZE, false);
+        tx_channel.set_peripheral_address(address, false);
+        tx_channel.set_memory_address(ptr, true);
+        tx_channel.set_transfer_length(len);
+
+        // A DMA transfer of length 0 would never complete
+        let empty = len == 0;
+        if !empty {
+            // Drop a stale received word so it doesn't end up first in the buffer
+            let _ = self.spi.datar.read();
+            let _ = self.spi.statr.read();
+
+            rx_channel.start();
+            self.spi.ctlr2.modify(|_, w| w.rxdmaen().set_bit());
+            tx_channel.start();
+            self.spi.ctlr2.modify(|_, w| w.txdmaen().set_bit());
+        }
+
+        Transfer::new(
+            buffer,
+            SpiTxRxDma {
+                spi: self,
+                rx_channel,
+                tx_channel,
+                empty,
+            },
+        )
+    }
+}
+
+impl<PINS, W: DataSize> SpiTxDma<PINS, W> {
+    /// Return the SPI peripheral and the DMA channel once the transfer is over
+    pub fn release(self) -> (Spi<PINS, W>, dma::C3) {
+        (self.spi, self.channel)
+    }
+}
+
+impl<PINS, W: DataSize> SpiTxRxDma<PINS, W> {
+    /// Return the SPI peripheral and the receive and transmit DMA channels once the
+    /// transfer is over
+    pub fn release(self) -> (Spi<PINS, W>, dma::C2, dma::C3) {
+        (self.spi, self.rx_channel, self.tx_channel)
+    }
+}
+
+impl<PINS, W: DataSize> TransferPayload for SpiTxDma<PINS, W> {
+    type Error = Error;
+
+    fn is_done(&self) -> bool {
+        self.empty || self.channel.is_complete() || self.channel.has_error()
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let failed = self.channel.has_error();
+        self.channel.stop();
+        self.spi.spi.ctlr2.modify(|_, w| w.txdmaen().clear_bit());
+        if failed {
+            return Err(Error::Dma);
+        }
+        if self.empty {
+            return Ok(());
+        }
+
+        // Transfer complete only means the last word was moved to DATAR
+        self.spi.end_write()
+    }
+}
+
+impl<PINS, W: DataSize> TransferPayload for SpiTxRxDma<PINS, W> {
+    type Error = Error;
+
+    fn is_done(&self) -> bool {
+        self.empty
+            || self.rx_channel.is_complete()
+            || self.rx_channel.has_error()
+            || self.tx_channel.has_error()
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let failed = self.rx_channel.has_error() || self.tx_channel.has_error();
+        self.rx_channel.stop();
+        self.tx_channel.stop();
+        self.spi
+            .spi
+            .ctlr2
+            .modify(|_, w| w.rxdmaen().clear_bit().txdmaen().clear_bit());
+        if failed {
+            return Err(Error::Dma);
+        }
+        if self.empty {
+            return Ok(());
+        }
+
+        match self.spi.read_word() {
+            Err(nb::Error::Other(e)) => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl embedded_hal_1::spi::Error for Error {
+    fn kind(&self) -> embedded_hal_1::spi::ErrorKind {
+        use embedded_hal_1::spi::ErrorKind;
+
+        match self {
+            Error::Overrun => ErrorKind::Overrun,
+            Error::ModeFault => ErrorKind::ModeFault,
+            Error::Crc | Error::Dma => ErrorKind::Other,
+        }
+    }
+}
+
+impl<PINS, W: DataSize> embedded_hal_1::spi::ErrorType for Spi<PINS, W> {
+    type Error = Error;
+}
+
+impl<PINS: SPI1Pins, W: DataSize> embedded_hal_1::spi::SpiBus<W> for Spi<PINS, W> {
+    fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        for word in words.iter_mut() {
+            *word = self.exchange(W::default())?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        Spi::write(self, words)
+    }
+
+    /// Words past the end of `write` are sent as zeros, words past the end of `read` are
+    /// discarded
+    fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        for i in 0..read.len().max(write.len()) {
+            let word = self.exchange(write.get(i).copied().unwrap_or_default())?;
+            if let Some(r) = read.get_mut(i) {
+                *r = word;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        Spi::transfer(self, words)
+    }
+
+    /// Wait until the last word has been shifted out and BSY is cleared
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.wait_idle();
+        Ok(())
+    }
+}
+
+impl<PINS: SPI1Pins, W: DataSize> embedded_hal_nb::spi::FullDuplex<W> for Spi<PINS, W> {
+    fn read(&mut self) -> nb::Result<W, Error> {
+        self.read_word()
+    }
+
+    fn write(&mut self, word: W) -> nb::Result<(), Error> {
+        self.send_word(word)
+    }
+}
+
+impl<PINS: SPI1Pins, W: DataSize> embedded_hal_02::spi::FullDuplex<W> for Spi<PINS, W> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<W, Error> {
+        self.read_word()
+    }
+
+    fn send(&mut self, word: W) -> nb::Result<(), Error> {
+        self.send_word(word)
+    }
+}
+
+// Implemented per word type, a generic impl would overlap the blanket impls for
+// `FullDuplex` types in embedded-hal 0.2
+macro_rules! spi_blocking_02 {
+    ($($W:ty),+) => {
+        $(
+            impl<PINS: SPI1Pins> embedded_hal_02::blocking::spi::Transfer<$W> for Spi<PINS, $W> {
+                type Error = Error;
+
+                fn transfer<'w>(&mut self, words: &'w mut [$W]) -> Result<&'w [$W], Error> {
+                    Spi::transfer(self, words)?;
+                    Ok(words)
+                }
+            }
+
+            impl<PINS: SPI1Pins> embedded_hal_02::blocking::spi::Write<$W> for Spi<PINS, $W> {
+                type Error = Error;
+
+                fn write(&mut self, words: &[$W]) -> Result<(), Error> {
+                    Spi::write(self, words)
+                }
+            }
+        )+
+    };
+}
+
+spi_blocking_02!(u8, u16);
+
+/// Smallest BR value dividing `pclk` down to at most `freq`
+fn prescaler(pclk: HertzU32, freq: HertzU32) -> Result<u8, SpiConfigError> {
+    let (pclk, freq) = (pclk.raw(), freq.raw());
+    (0..8)
+        .find(|br| pclk >> (br + 1) <= freq)
+        .ok_or(SpiConfigError::UnachievableFrequency)
+}
+
+/// SCK and the data line (MOSI) of SPI1 in bidirectional mode, see [`Spi::new_bidi`]
+pub type BidiPins = (PC5<Alternate<PushPull>>, PC6<Alternate<PushPull>>);
+
+/// SCK, MISO and MOSI pins of SPI1, optionally with the NSS pin for [`NssMode::HardwareOutput`]
+pub trait SPI1Pins: crate::Sealed {
+    /// Value of the remap bit (SPI1_RM)
+    const REMAP: bool;
+    /// The NSS pin is included
+    const NSS: bool;
+
+    /// Raise the slew rate of SCK and MOSI to 50 MHz
+    fn require_speed(&mut self);
+}
+
+/// Software chip select, the pins are the same for both remap options
+impl SPI1Pins
+    for (
+        PC5<Alternate<PushPull>>,
+        PC7<Input<Floating>>,
+        PC6<Alternate<PushPull>>,
+    )
+{
+    const REMAP: bool = false;
+    const NSS: bool = false;
+
+    fn require_speed(&mut self) {
+        self.0.require_speed(Speed::Mhz50);
+        self.2.require_speed(Speed::Mhz50);
+    }
+}
+
+impl crate::Sealed
+    for (
+        PC5<Alternate<PushPull>>,
+        PC7<Input<Floating>>,
+        PC6<Alternate<PushPull>>,
+    )
+{
+}
+
+/// Default pin remapping option, hardware NSS on PC1
+impl SPI1Pins
+    for (
+        PC5<Alternate<PushPull>>,
+        PC7<Input<Floating>>,
+        PC6<Alternate<PushPull>>,
+        PC1<Alternate<PushPull>>,
+    )
+{
+    const REMAP: bool = false;
+    const NSS: bool = true;
+
+    fn require_speed(&mut self) {
+        self.0.require_speed(Speed::Mhz50);
+        self.2.require_speed(Speed::Mhz50);
+    }
+}
+
+impl crate::Sealed
+    for (
+        PC5<Alternate<PushPull>>,
+        PC7<Input<Floating>>,
+        PC6<Alternate<PushPull>>,
+        PC1<Alternate<PushPull>>,
+    )
+{
+}
+
+/// Pin remapping option 1, hardware NSS on PC0
+impl SPI1Pins
+    for (
+        PC5<Alternate<PushPull>>,
+        PC7<Input<Floating>>,
+        PC6<Alternate<PushPull>>,
+        PC0<Alternate<PushPull>>,
+    )
+{
+    const REMAP: bool = true;
+    const NSS: bool = true;
+
+    fn require_speed(&mut self) {
+        self.0.require_speed(Speed::Mhz50);
+        self.2.require_speed(Speed::Mhz50);
+    }
+}
+
+impl crate::Sealed
+    for (
+        PC5<Alternate<PushPull>>,
+        PC7<Input<Floating>>,
+        PC6<Alternate<PushPull>>,
+        PC0<Alternate<PushPull>>,
+    )
+{
+}
+
+/// SCK, MISO and MOSI pins of SPI1 in slave mode, optionally with the NSS input
+pub trait SPI1SlavePins: crate::Sealed {
+    /// Value of the remap bit (SPI1_RM)
+    const REMAP: bool;
+    /// The NSS pin is included
+    const NSS: bool;
+
+    /// Raise the slew rate of MISO to 50 MHz
+    fn require_speed(&mut self);
+}
+
+/// Always selected, the pins are the same for both remap options
+impl SPI1SlavePins
+    for (
+        PC5<Input<Floating>>,
+        PC7<Alternate<PushPull>>,
+        PC6<Input<Floating>>,
+    )
+{
+    const REMAP: bool = false;
+    const NSS: bool = false;
+
+    fn require_speed(&mut self) {
+        self.1.require_speed(Speed::Mhz50);
+    }
+}
+
+impl crate::Sealed
+    for (
+        PC5<Input<Floating>>,
+        PC7<Alternate<PushPull>>,
+        PC6<Input<Floating>>,
+    )
+{
+}
+
+/// Default pin remapping option, NSS on PC1
+impl SPI1SlavePins
+    for (
+        PC5<Input<Floating>>,
+        PC7<Alternate<PushPull>>,
+        PC6<Input<Floating>>,
+        PC1<Input<Floating>>,
+    )
+{
+    const REMAP: bool = false;
+    const NSS: bool = true;
+
+    fn require_speed(&mut self) {
+        self.1.require_speed(Speed::Mhz50);
+    }
+}
+
+impl crate::Sealed
+    for (
+        PC5<Input<Floating>>,
+        PC7<Alternate<PushPull>>,
+        PC6<Input<Floating>>,
+        PC1<Input<Floating>>,
+    )
+{
+}
+
+/// Pin remapping option 1, NSS on PC0
+impl SPI1SlavePins
+    for (
+        PC5<Input<Floating>>,
+        PC7<Alternate<PushPull>>,
+        PC6<Input<Floating>>,
+        PC0<Input<Floating>>,
+    )
+{
+    const REMAP: bool = true;
+    const NSS: bool = true;
+
+    fn require_speed(&mut self) {
+        self.1.require_speed(Speed::Mhz50);
+    }
+}
+
+impl crate::Sealed
+    for (
+        PC5<Input<Floating>>,
+        PC7<Alternate<PushPull>>,
+        PC6<Input<Floating>>,
+        PC0<Input<Floating>>,
+    )
+{
+}
@@ -0,0 +1,208 @@
+//! Input capture
+//!
+//! [`Timer::input_capture`] copies the counter into the compare register of a channel on an
+//! edge of its pin. The counter runs independently, e.g. started with [`Timer::start`] at
+//! a low frequency for a long overflow period: captures count [`Timer::tick_rate`] ticks and
+//! a pulse width or period is the wrapping difference of two captures.
+
+use core::marker::PhantomData;
+
+use super::{channel_remap, CInPin, Error, Timer, C1, C2, C3};
+use crate::{
+    afio::PCFR,
+    pac::{TIM1, TIM2},
+};
+
+/// Input edge captured by a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureEdge {
+    /// Low to high
+    #[default]
+    Rising,
+    /// High to low
+    Falling,
+}
+
+/// Number of edges per capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CapturePrescaler {
+    /// Capture every edge
+    #[default]
+    Div1 = 0b00,
+    /// Capture every 2 edges
+    Div2 = 0b01,
+    /// Capture every 4 edges
+    Div4 = 0b10,
+    /// Capture every 8 edges
+    Div8 = 0b11,
+}
+
+/// Input capture configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CaptureConfig {
+    /// Captured edge
+    pub edge: CaptureEdge,
+    /// Edges per capture
+    pub prescaler: CapturePrescaler,
+    /// Digital input filter, 0 (none) to 15 (ICxF)
+    ///
+    /// An edge is only seen after the input is stable for a number of samples, see the
+    /// reference manual for the sampling rates of each value.
+    pub filter: u8,
+}
+
+impl CaptureConfig {
+    /// Capture the given edge
+    pub const fn edge(mut self, edge: CaptureEdge) -> Self {
+        self.edge = edge;
+        self
+    }
+
+    /// Capture once every few edges
+    pub const fn prescaler(mut self, prescaler: CapturePrescaler) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Filter the input, 0 to 15
+    pub const fn filter(mut self, filter: u8) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+/// Input capture channel `C` of a timer
+pub struct InputCapture<TIM, const C: u8> {
+    _tim: PhantomData<TIM>,
+}
+
+macro_rules! capture {
+    ($($TIMX:ident: ($remap:ident, $set_remap:ident),)+) => {
+        $(
+            impl Timer<$TIMX> {
+                /// Capture the counter on channel `C` when `pin` sees the configured edge
+                ///
+                /// The current remap of the timer is kept if the pin is connected under it,
+                /// otherwise it is selected for the pin while no other channel is enabled.
+                /// Pins that are on several channels need the channel as turbofish, e.g.
+                /// `input_capture::<C1, _>`.
+                ///
+                /// # Panics
+                ///
+                /// Panics if the filter is larger than 15, or if the pin would need another
+                /// remap while other channels are enabled.
+                pub fn input_capture<const C: u8, PIN: CInPin<$TIMX, C>>(
+                    &mut self,
+                    _pin: PIN,
+                    config: CaptureConfig,
+                    pcfr: &mut PCFR,
+                ) -> InputCapture<$TIMX, C> {
+                    assert!(config.filter < 16);
+                    let others = self.tim.ccer.read().bits() & !(0b1111 << (4 * C));
+                    let remap = channel_remap(pcfr.$remap(), PIN::REMAPS, others);
+                    pcfr.$set_remap(remap);
+
+                    let ccer = 0b11 << (4 * C);
+                    self.tim
+                        .ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !ccer) });
+
+                    // CCxS = 0b01 selects the input of the channel itself
+                    let mode = (config.filter as u32) << 4 | (config.prescaler as u32) << 2 | 0b01;
+                    let shift = 8 * (C as u32 % 2);
+                    if C < C3 {
+                        self.tim.chctlr1_input().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | mode << shift)
+                        });
+                    } else {
+                        self.tim.chctlr2_input().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | mode << shift)
+                        });
+                    }
+
+                    // CCxP selects the falling edge, CCxE enables the capture
+                    let falling = (config.edge == CaptureEdge::Falling) as u32;
+                    self.tim.ccer.modify(|r, w| unsafe {
+                        w.bits(r.bits() | (falling << 1 | 1) << (4 * C))
+                    });
+                    self.clear_capture_flags::<C>();
+
+                    InputCapture { _tim: PhantomData }
+                }
+
+                fn clear_capture_flags<const C: u8>(&mut self) {
+                    // NOTE(unsafe) rc_w0 flags, writing 1 leaves the others unchanged
+                    self.tim
+                        .intfr
+                        .write(|w| unsafe { w.bits(!(1 << (1 + C) | 1 << (9 + C))) });
+                }
+            }
+
+            impl<const C: u8> InputCapture<$TIMX, C> {
+                #[inline(always)]
+                fn tim(&self) -> &<$TIMX as core::ops::Deref>::Target {
+                    // NOTE(unsafe) the channel only accesses its own bits, or its own register
+                    unsafe { &*$TIMX::ptr() }
+                }
+
+                /// The last captured counter value, once a new one is available
+                ///
+                /// Reading the capture clears its flag. If an edge was captured again before
+                /// the previous value was read, the overcapture flag is cleared and
+                /// [`Error::Overcapture`] returned, the next call returns the latest capture.
+                pub fn read_capture(&mut self) -> nb::Result<u16, Error> {
+                    let tim = self.tim();
+                    let intfr = tim.intfr.read().bits();
+                    if intfr & 1 << (9 + C) != 0 {
+                        tim.intfr.write(|w| unsafe { w.bits(!(1 << (9 + C))) });
+                        return Err(nb::Error::Other(Error::Overcapture));
+                    }
+                    if intfr & 1 << (1 + C) == 0 {
+                        return Err(nb::Error::WouldBlock);
+                    }
+                    Ok(match C {
+                        C1 => tim.ch1cvr.read().bits() as u16,
+                        C2 => tim.ch2cvr.read().bits() as u16,
+                        C3 => tim.ch3cvr.read().bits() as u16,
+                        _ => tim.ch4cvr.read().bits() as u16,
+                    })
+                }
+
+                /// Raise the timer interrupt on each capture (CCxIE)
+                ///
+                /// The handler clears the flag by calling [`read_capture`](Self::read_capture).
+                pub fn listen(&mut self) {
+                    self.tim()
+                        .dmaintenr
+                        .modify(|r, w| unsafe { w.bits(r.bits() | 1 << (1 + C)) });
+                }
+
+                /// Stop raising the timer interrupt on captures
+                pub fn unlisten(&mut self) {
+                    self.tim()
+                        .dmaintenr
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (1 + C))) });
+                }
+
+                /// Stop capturing (CCxE)
+                pub fn disable(&mut self) {
+                    self.tim()
+                        .ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (4 * C))) });
+                }
+
+                /// Resume capturing
+                pub fn enable(&mut self) {
+                    self.tim()
+                        .ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() | 1 << (4 * C)) });
+                }
+            }
+        )+
+    };
+}
+
+capture! {
+    TIM1: (tim1_remap, set_tim1_remap),
+    TIM2: (tim2_remap, set_tim2_remap),
+}
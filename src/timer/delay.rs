@@ -1,86 +1,108 @@
-//! Delays
-use crate::pac::SYSTICK;
-use crate::rcc::Clocks;
+//! Blocking delays counted by a timer
 
-/// A delay using the systick timer
+use fugit::HertzU32 as Hertz;
+
+use super::Timer;
+use crate::pac::{TIM1, TIM2};
+
+/// A blocking delay counting timer clock cycles
 ///
-/// Timing mostly doesn't vary from interrupts
-pub struct SysDelay {
-    systick: SYSTICK,
-    scale: u32,
+/// Unlike [`CycleDelay`](crate::delay::CycleDelay), the delay doesn't depend on the
+/// instruction timing and interrupts only lengthen it by the time spent in their handler
+/// past the end of the delay.
+pub struct Delay<TIM> {
+    timer: Timer<TIM>,
 }
 
-impl SysDelay {
-    pub fn new(systick: SYSTICK, clocks: &Clocks) -> Self {
-        systick.ctlr.write(|w| w.stclk().set_bit().ste().set_bit());
-        let scale = clocks.hclk().to_MHz() as u32;
-        SysDelay {
-            systick,
-            scale
-        }
-    }
+/// Number of timer clock cycles in `ns`, rounded up
+fn delay_cycles(clk: Hertz, ns: u32) -> u64 {
+    (ns as u64 * clk.raw() as u64).div_ceil(1_000_000_000)
 }
 
-impl embedded_hal_alpha::delay::DelayUs for SysDelay {
-    fn delay_us(&mut self, mut us: u32) {
-        // Wait at most for half the potential length (to avoid issues when interrupts occur and the timer continues)
-        // To avoid having to calculate and/or store the maximum depending on the scale, just pick the worst case.
-        // This is around 45 seconds, so this shouldn't be an issue
-        const MAX_US: u32 = 8000_0000u32 / 48;
-        // Scale the us inside the loop, to avoid overflow scenarios
-        while us != 0 {
-            let current_us = us.min(MAX_US);
-            let current_rvr = current_us * self.scale;
-
-            let start_rvr = self.systick.cnt.read().cnt().bits();
-            // Update the tracking variable while we are waiting...
-            us -= current_us;
-            // Use the wrapping substraction to deal with the systick wrapping around
-            while (self.systick.cnt.read().cnt().bits().wrapping_sub(start_rvr)) < current_rvr {}
-        }
-    }
-}
+macro_rules! delay {
+    ($($TIMX:ident,)+) => {
+        $(
+            impl Timer<$TIMX> {
+                /// Use the timer for blocking delays, with the resolution of one PCLK cycle
+                pub fn delay(self) -> Delay<$TIMX> {
+                    let tim = &self.tim;
+                    tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+                    tim.psc.write(|w| w.psc().variant(0));
+                    // The counter stops by itself at the end of each period (OPM), URS keeps
+                    // the update events of UG from setting the interrupt flag
+                    tim.ctlr1
+                        .modify(|_, w| w.opm().set_bit().urs().set_bit().arpe().clear_bit());
 
-impl embedded_hal::blocking::delay::DelayUs<u32> for SysDelay {
-    fn delay_us(&mut self, us: u32) {
-        embedded_hal_alpha::delay::DelayUs::delay_us(self, us as _);
-    }
-}
+                    Delay { timer: self }
+                }
+            }
 
-impl embedded_hal::blocking::delay::DelayUs<u16> for SysDelay {
-    fn delay_us(&mut self, us: u16) {
-        embedded_hal_alpha::delay::DelayUs::delay_us(self, us as _);
-    }
-}
+            impl Delay<$TIMX> {
+                /// Leave one-pulse mode and return the stopped timer
+                pub fn release(self) -> Timer<$TIMX> {
+                    let tim = &self.timer.tim;
+                    tim.ctlr1.modify(|_, w| w.cen().clear_bit().opm().clear_bit());
+                    self.timer
+                }
+            }
 
-impl embedded_hal::blocking::delay::DelayUs<u8> for SysDelay {
-    fn delay_us(&mut self, us: u8) {
-        embedded_hal_alpha::delay::DelayUs::delay_us(self, us as _);
-    }
-}
+            impl embedded_hal_1::delay::DelayNs for Delay<$TIMX> {
+                fn delay_ns(&mut self, ns: u32) {
+                    let tim = &self.timer.tim;
+                    let mut cycles = delay_cycles(self.timer.clk, ns);
 
-impl embedded_hal::blocking::delay::DelayMs<u32> for SysDelay {
-    // Multiplying ms so we can call delay_us directly might overflow, so implement an outer loop
-    fn delay_ms(&mut self, mut ms: u32) {
-        const MAX_MS: u32 = 0x0010_0000;
-        while ms != 0 {
-            let current_ms = if ms <= MAX_MS { ms } else { MAX_MS };
-            embedded_hal::blocking::delay::DelayUs::delay_us(self, current_ms as u32 * 1_000);
-            ms -= current_ms;
-        }
-    }
-}
+                    // Periods shorter than 2 cycles can't be counted, and don't matter
+                    while cycles >= 2 {
+                        let period = cycles.min(0x10000);
+                        tim.atrlr.write(|w| w.atrlr().variant((period - 1) as u16));
+                        tim.swevgr.write(|w| w.ug().set_bit());
+                        tim.ctlr1.modify(|_, w| w.cen().set_bit());
+                        while tim.ctlr1.read().cen().bit_is_set() {}
+                        cycles -= period;
+                    }
+                }
+            }
+
+            impl embedded_hal_02::blocking::delay::DelayUs<u32> for Delay<$TIMX> {
+                fn delay_us(&mut self, us: u32) {
+                    embedded_hal_1::delay::DelayNs::delay_us(self, us);
+                }
+            }
+
+            impl embedded_hal_02::blocking::delay::DelayUs<u16> for Delay<$TIMX> {
+                fn delay_us(&mut self, us: u16) {
+                    embedded_hal_1::delay::DelayNs::delay_us(self, us as u32);
+                }
+            }
+
+            impl embedded_hal_02::blocking::delay::DelayUs<u8> for Delay<$TIMX> {
+                fn delay_us(&mut self, us: u8) {
+                    embedded_hal_1::delay::DelayNs::delay_us(self, us as u32);
+                }
+            }
+
+            impl embedded_hal_02::blocking::delay::DelayMs<u32> for Delay<$TIMX> {
+                fn delay_ms(&mut self, ms: u32) {
+                    embedded_hal_1::delay::DelayNs::delay_ms(self, ms);
+                }
+            }
+
+            impl embedded_hal_02::blocking::delay::DelayMs<u16> for Delay<$TIMX> {
+                fn delay_ms(&mut self, ms: u16) {
+                    embedded_hal_1::delay::DelayNs::delay_ms(self, ms as u32);
+                }
+            }
 
-impl embedded_hal::blocking::delay::DelayMs<u16> for SysDelay {
-    fn delay_ms(&mut self, ms: u16) {
-        // Call delay_us directly, so we don't have to use the additional
-        // delay loop the u32 variant uses
-        embedded_hal::blocking::delay::DelayUs::delay_us(self, ms as u32 * 1_000);
-    }
+            impl embedded_hal_02::blocking::delay::DelayMs<u8> for Delay<$TIMX> {
+                fn delay_ms(&mut self, ms: u8) {
+                    embedded_hal_1::delay::DelayNs::delay_ms(self, ms as u32);
+                }
+            }
+        )+
+    };
 }
 
-impl embedded_hal::blocking::delay::DelayMs<u8> for SysDelay {
-    fn delay_ms(&mut self, ms: u8) {
-        self.delay_ms(ms as u16);
-    }
+delay! {
+    TIM1,
+    TIM2,
 }
@@ -0,0 +1,132 @@
+//! Quadrature encoder interface
+
+use super::{CInPin, Timer, C1, C2};
+use crate::{
+    afio::PCFR,
+    pac::{TIM1, TIM2},
+};
+
+/// Edges counted in encoder mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncoderMode {
+    /// Count both edges of input A, two counts per cycle of the encoder (SMS = 0b010)
+    X2 = 0b010,
+    /// Count both edges of both inputs, four counts per cycle of the encoder (SMS = 0b011)
+    #[default]
+    X4 = 0b011,
+}
+
+/// Counting direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The count increases
+    Up,
+    /// The count decreases
+    Down,
+}
+
+/// A timer counting the steps of a quadrature encoder
+///
+/// The count wraps between 0 and 0xffff, read it often enough to track the difference
+/// between two reads with `wrapping_sub`.
+pub struct Encoder<TIM> {
+    timer: Timer<TIM>,
+}
+
+macro_rules! encoder {
+    ($($TIMX:ident: $set_remap:ident,)+) => {
+        $(
+            impl Timer<$TIMX> {
+                /// Count the quadrature signal on `pin_a` (channel 1) and `pin_b` (channel 2)
+                ///
+                /// The count starts at zero. Swap the pins to reverse the direction.
+                ///
+                /// # Panics
+                ///
+                /// Panics if the pins are not connected under the same remap.
+                pub fn encoder<A: CInPin<$TIMX, C1>, B: CInPin<$TIMX, C2>>(
+                    self,
+                    _pin_a: A,
+                    _pin_b: B,
+                    mode: EncoderMode,
+                    pcfr: &mut PCFR,
+                ) -> Encoder<$TIMX> {
+                    let remaps = A::REMAPS & B::REMAPS;
+                    assert!(remaps != 0, "the encoder pins have no common remap");
+                    pcfr.$set_remap(remaps.trailing_zeros() as u8);
+
+                    let tim = &self.tim;
+                    tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+                    tim.smcfgr.modify(|_, w| unsafe { w.sms().bits(0) });
+
+                    // Both channels on their own input, not inverted
+                    tim.chctlr1_input()
+                        .modify(|_, w| unsafe { w.cc1s().bits(0b01).cc2s().bits(0b01) });
+                    tim.ccer.modify(|_, w| w.cc1p().clear_bit().cc2p().clear_bit());
+
+                    tim.psc.write(|w| w.psc().variant(0));
+                    tim.atrlr.write(|w| w.atrlr().variant(0xffff));
+                    tim.ctlr1.modify(|_, w| w.urs().set_bit());
+                    tim.swevgr.write(|w| w.ug().set_bit());
+                    tim.intfr.write(|w| unsafe { w.bits(!1) });
+                    tim.cnt.reset();
+
+                    tim.smcfgr
+                        .modify(|_, w| unsafe { w.sms().bits(mode as u8) });
+                    tim.ctlr1.modify(|_, w| w.cen().set_bit());
+
+                    Encoder { timer: self }
+                }
+            }
+
+            impl Encoder<$TIMX> {
+                /// Current count
+                pub fn count(&self) -> u16 {
+                    self.timer.tim.cnt.read().bits() as u16
+                }
+
+                /// Overwrite the count
+                pub fn set_count(&mut self, count: u16) {
+                    self.timer.tim.cnt.write(|w| w.cnt().variant(count));
+                }
+
+                /// Direction of the last step
+                pub fn direction(&self) -> Direction {
+                    if self.timer.tim.ctlr1.read().dir().bit_is_set() {
+                        Direction::Down
+                    } else {
+                        Direction::Up
+                    }
+                }
+
+                /// Leave encoder mode and return the stopped timer
+                pub fn release(self) -> Timer<$TIMX> {
+                    let tim = &self.timer.tim;
+                    tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+                    tim.smcfgr.modify(|_, w| unsafe { w.sms().bits(0) });
+                    self.timer
+                }
+            }
+
+            impl embedded_hal_02::Qei for Encoder<$TIMX> {
+                type Count = u16;
+
+                fn count(&self) -> u16 {
+                    Encoder::<$TIMX>::count(self)
+                }
+
+                fn direction(&self) -> embedded_hal_02::Direction {
+                    match Encoder::<$TIMX>::direction(self) {
+                        Direction::Up => embedded_hal_02::Direction::Upcounting,
+                        Direction::Down => embedded_hal_02::Direction::Downcounting,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+encoder! {
+    TIM1: set_tim1_remap,
+    TIM2: set_tim2_remap,
+}
@@ -1,2 +1,218 @@
-// Disabled due to depending on https://github.com/ch32-rs/ch32-rs/pull/15
-//pub mod delay;
+//! Timers
+//!
+//! TIM1 (advanced) and TIM2 (general purpose) are 16 bit timers clocked from PCLK. A
+//! [`Timer`] counts periodically at a given frequency, as an embedded-hal `CountDown`
+//! or as an interrupt source, drives up to four PWM outputs ([`Timer::pwm`]) or captures
+//! the counter on input edges ([`Timer::input_capture`]). In encoder mode
+//! ([`Timer::encoder`]) it counts the steps of a quadrature encoder, in one-pulse mode
+//! ([`Timer::one_pulse`]) it outputs single pulses, or it times blocking delays
+//! ([`Timer::delay`]).
+
+mod capture;
+mod delay;
+mod encoder;
+mod one_pulse;
+mod pins;
+mod pwm;
+
+pub use capture::{CaptureConfig, CaptureEdge, CapturePrescaler, InputCapture};
+pub use delay::Delay;
+pub use encoder::{Direction, Encoder, EncoderMode};
+pub use one_pulse::OnePulse;
+pub use pins::{BkinPin, CInPin, CNPin, CPin};
+pub use pwm::{BreakPolarity, PwmChannel, PwmPins};
+
+use fugit::HertzU32 as Hertz;
+
+use crate::{
+    pac::{TIM1, TIM2},
+    rcc::{BusClock, Clocks, Enable, Rcc},
+};
+
+/// Channel 1
+pub const C1: u8 = 0;
+/// Channel 2
+pub const C2: u8 = 1;
+/// Channel 3
+pub const C3: u8 = 2;
+/// Channel 4
+pub const C4: u8 = 3;
+
+/// Interrupt events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The counter reached the end of its period
+    Update,
+}
+
+/// Timer error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The timer is already stopped
+    Disabled,
+    /// A capture was overwritten before it was read
+    Overcapture,
+}
+
+/// Hardware timer, counting periodically once started
+pub struct Timer<TIM> {
+    tim: TIM,
+    clk: Hertz,
+}
+
+/// A timer peripheral, TIM1 or TIM2
+pub trait Instance: BusClock + crate::Sealed {
+    #[doc(hidden)]
+    fn enable(rcc: &mut Rcc);
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// Enable the clock of the timer
+    pub fn new(tim: TIM, rcc: &mut Rcc, clocks: &Clocks) -> Self {
+        TIM::enable(rcc);
+
+        Self {
+            tim,
+            clk: TIM::clock(clocks),
+        }
+    }
+}
+
+/// Prescaler and auto-reload values for a period of `ticks` timer clock cycles
+///
+/// The prescaler is kept as small as possible for the best resolution, with a period of at
+/// most `max_period` cycles.
+fn psc_arr(ticks: u32, max_period: u32) -> (u16, u16) {
+    let ticks = ticks.max(1);
+    let psc = (ticks - 1) / max_period;
+    let arr = ticks / (psc + 1) - 1;
+    (psc.min(0xffff) as u16, arr.clamp(0, 0xffff) as u16)
+}
+
+/// Remap for a single channel pin connected under the remaps `remaps`
+///
+/// The `current` remap is kept if the pin is connected under it. Otherwise it is only
+/// changed if no other channel is enabled in `ccer`, which would be disconnected.
+fn channel_remap(current: u8, remaps: u8, ccer: u32) -> u8 {
+    if remaps & 1 << current != 0 {
+        return current;
+    }
+    // CCxE and CCxNE bits
+    assert!(
+        ccer & 0x1555 == 0,
+        "the pin isn't connected under the remap used by the other channels"
+    );
+    remaps.trailing_zeros() as u8
+}
+
+macro_rules! hal {
+    ($($TIMX:ident: $apbX:ident,)+) => {
+        $(
+            impl Instance for $TIMX {
+                fn enable(rcc: &mut Rcc) {
+                    <$TIMX as Enable>::enable(&mut rcc.$apbX);
+                }
+            }
+
+            impl Timer<$TIMX> {
+                /// Count periodically at `freq`, restarting from zero
+                ///
+                /// The frequency is rounded down to a whole number of timer clock cycles.
+                pub fn start(&mut self, freq: Hertz) {
+                    self.tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+
+                    let (psc, arr) = psc_arr(self.clk.raw() / freq.raw().max(1), 1 << 16);
+                    self.tim.psc.write(|w| w.psc().variant(psc));
+                    self.tim.atrlr.write(|w| w.atrlr().variant(arr));
+
+                    // Load the new values with an update event, URS keeps it from setting
+                    // the interrupt flag
+                    self.tim.ctlr1.modify(|_, w| w.urs().set_bit());
+                    self.tim.swevgr.write(|w| w.ug().set_bit());
+                    self.tim.intfr.write(|w| unsafe { w.bits(!1) });
+
+                    self.tim.ctlr1.modify(|_, w| w.cen().set_bit());
+                }
+
+                /// Rate at which the counter increments, PCLK divided by the prescaler
+                pub fn tick_rate(&self) -> Hertz {
+                    self.clk / (self.tim.psc.read().bits() + 1)
+                }
+
+                /// Whether a period has ended, clearing the flag if so
+                pub fn wait(&mut self) -> nb::Result<(), void::Void> {
+                    if self.tim.intfr.read().uif().bit_is_clear() {
+                        Err(nb::Error::WouldBlock)
+                    } else {
+                        self.tim.intfr.write(|w| unsafe { w.bits(!1) });
+                        Ok(())
+                    }
+                }
+
+                /// Stop counting
+                pub fn cancel(&mut self) -> Result<(), Error> {
+                    if self.tim.ctlr1.read().cen().bit_is_clear() {
+                        return Err(Error::Disabled);
+                    }
+                    self.tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+                    Ok(())
+                }
+
+                /// Raise the timer interrupt on `event`
+                pub fn listen(&mut self, event: Event) {
+                    match event {
+                        Event::Update => self.tim.dmaintenr.modify(|_, w| w.uie().set_bit()),
+                    }
+                }
+
+                /// Stop raising the timer interrupt on `event`
+                pub fn unlisten(&mut self, event: Event) {
+                    match event {
+                        Event::Update => self.tim.dmaintenr.modify(|_, w| w.uie().clear_bit()),
+                    }
+                }
+
+                /// Clear the flag of `event`, which has to be done in the interrupt handler
+                pub fn clear_interrupt(&mut self, event: Event) {
+                    match event {
+                        // NOTE(unsafe) rc_w0 flags, writing 1 leaves the others unchanged
+                        Event::Update => self.tim.intfr.write(|w| unsafe { w.bits(!1) }),
+                    }
+                }
+
+                /// Stop the timer and return the peripheral
+                pub fn release(self) -> $TIMX {
+                    self.tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+                    self.tim
+                }
+            }
+
+            impl embedded_hal_02::timer::CountDown for Timer<$TIMX> {
+                type Time = Hertz;
+
+                fn start<T: Into<Hertz>>(&mut self, freq: T) {
+                    Timer::<$TIMX>::start(self, freq.into());
+                }
+
+                fn wait(&mut self) -> nb::Result<(), void::Void> {
+                    Timer::<$TIMX>::wait(self)
+                }
+            }
+
+            impl embedded_hal_02::timer::Periodic for Timer<$TIMX> {}
+
+            impl embedded_hal_02::timer::Cancel for Timer<$TIMX> {
+                type Error = Error;
+
+                fn cancel(&mut self) -> Result<(), Error> {
+                    Timer::<$TIMX>::cancel(self)
+                }
+            }
+        )+
+    };
+}
+
+hal! {
+    TIM1: apb2,
+    TIM2: apb1,
+}
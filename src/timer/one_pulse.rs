@@ -0,0 +1,117 @@
+//! One-pulse mode
+
+use fugit::MicrosDurationU32;
+
+use super::{channel_remap, CPin, Timer, C1, C2, C3};
+use crate::{
+    afio::PCFR,
+    pac::{TIM1, TIM2},
+};
+
+/// A timer outputting a single pulse on channel `C` each time it is triggered
+pub struct OnePulse<TIM, const C: u8> {
+    timer: Timer<TIM>,
+}
+
+macro_rules! one_pulse {
+    ($($TIMX:ident: ($remap:ident, $set_remap:ident $(, $moe:ident)?),)+) => {
+        $(
+            impl Timer<$TIMX> {
+                /// Output a high pulse of `width` on `pin`, `delay` after each
+                /// [`trigger`](OnePulse::trigger)
+                ///
+                /// The counter stops at the end of the pulse (OPM) and the output returns low.
+                /// The prescaler is chosen for the sum of both durations, up to about 178 s
+                /// at 24 MHz, and the delay is at least one timer tick. Pins that
+                /// are on several channels need the channel as turbofish, e.g.
+                /// `one_pulse::<C1, _>`.
+                ///
+                /// The current remap of the timer is kept if the pin is connected under it,
+                /// otherwise it is selected for the pin while no other channel is enabled.
+                ///
+                /// # Panics
+                ///
+                /// Panics if the pin would need another remap while other channels are
+                /// enabled.
+                pub fn one_pulse<const C: u8, PIN: CPin<$TIMX, C>>(
+                    self,
+                    _pin: PIN,
+                    delay: MicrosDurationU32,
+                    width: MicrosDurationU32,
+                    pcfr: &mut PCFR,
+                ) -> OnePulse<$TIMX, C> {
+                    let others = self.tim.ccer.read().bits() & !(0b1111 << (4 * C));
+                    let remap = channel_remap(pcfr.$remap(), PIN::REMAPS, others);
+                    pcfr.$set_remap(remap);
+
+                    let tim = &self.tim;
+                    tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+
+                    let clk = self.clk.raw() as u64;
+                    let total = (delay.ticks() as u64 + width.ticks() as u64) * clk / 1_000_000;
+                    let psc = (total.max(1) - 1) / (1 << 16);
+                    let tick = clk / (psc + 1);
+                    let ccr = (delay.ticks() as u64 * tick / 1_000_000).clamp(1, 0xfffe);
+                    let width = (width.ticks() as u64 * tick / 1_000_000).max(1);
+                    let arr = (ccr + width - 1).min(0xffff);
+
+                    tim.psc.write(|w| w.psc().variant(psc.min(0xffff) as u16));
+                    tim.atrlr.write(|w| w.atrlr().variant(arr as u16));
+                    match C {
+                        C1 => tim.ch1cvr.write(|w| w.ch1cvr().variant(ccr as u16)),
+                        C2 => tim.ch2cvr.write(|w| w.ch2cvr().variant(ccr as u16)),
+                        C3 => tim.ch3cvr.write(|w| w.ch3cvr().variant(ccr as u16)),
+                        _ => tim.ch4cvr.write(|w| w.ch4cvr().variant(ccr as u16)),
+                    }
+
+                    // PWM mode 2 (OCxM = 0b111): low until the counter reaches the delay
+                    let shift = 8 * (C as u32 % 2);
+                    if C < C3 {
+                        tim.chctlr1_output().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | 0x70 << shift)
+                        });
+                    } else {
+                        tim.chctlr2_output().modify(|r, w| unsafe {
+                            w.bits(r.bits() & !(0xff << shift) | 0x70 << shift)
+                        });
+                    }
+                    tim.ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() | 1 << (4 * C)) });
+                    $(tim.bdtr.modify(|_, w| w.$moe().set_bit());)?
+
+                    tim.ctlr1.modify(|_, w| w.opm().set_bit().urs().set_bit());
+                    tim.swevgr.write(|w| w.ug().set_bit());
+                    tim.intfr.write(|w| unsafe { w.bits(!1) });
+
+                    OnePulse { timer: self }
+                }
+            }
+
+            impl<const C: u8> OnePulse<$TIMX, C> {
+                /// Start a pulse, unless one is in progress
+                pub fn trigger(&mut self) {
+                    self.timer.tim.ctlr1.modify(|_, w| w.cen().set_bit());
+                }
+
+                /// Whether a pulse is in progress
+                pub fn is_busy(&self) -> bool {
+                    self.timer.tim.ctlr1.read().cen().bit_is_set()
+                }
+
+                /// Disable the output and one-pulse mode, and return the timer
+                pub fn release(self) -> Timer<$TIMX> {
+                    let tim = &self.timer.tim;
+                    tim.ctlr1.modify(|_, w| w.cen().clear_bit().opm().clear_bit());
+                    tim.ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (4 * C))) });
+                    self.timer
+                }
+            }
+        )+
+    };
+}
+
+one_pulse! {
+    TIM1: (tim1_remap, set_tim1_remap, moe),
+    TIM2: (tim2_remap, set_tim2_remap),
+}
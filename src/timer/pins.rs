@@ -0,0 +1,91 @@
+//! Timer channel pins
+
+use crate::{
+    gpio::{gpioa::*, gpioc::*, gpiod::*, Alternate, Input, NoPin, Pin, PushPull},
+    pac::{TIM1, TIM2},
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl<const P: char, const N: u8, MODE> sealed::Sealed for Pin<P, N, MODE> {}
+impl sealed::Sealed for NoPin {}
+
+/// Output pin of channel `C` ([`C1`](super::C1) to [`C4`](super::C4)) of a timer
+pub trait CPin<TIM, const C: u8>: sealed::Sealed {
+    /// Bit `n` is set if the pin is connected to the channel with the remap `n` (TIMx_RM)
+    const REMAPS: u8;
+}
+
+/// Input pin of channel `C` ([`C1`](super::C1) to [`C4`](super::C4)) of a timer
+pub trait CInPin<TIM, const C: u8>: sealed::Sealed {
+    /// Bit `n` is set if the pin is connected to the channel with the remap `n` (TIMx_RM)
+    const REMAPS: u8;
+}
+
+/// Complementary output pin of channel `C` ([`C1`](super::C1) to [`C3`](super::C3)) of TIM1
+pub trait CNPin<TIM, const C: u8>: sealed::Sealed {
+    /// Bit `n` is set if the pin is connected to the output with the remap `n` (TIMx_RM)
+    const REMAPS: u8;
+}
+
+/// Leaves the channel unconnected, under any remap
+impl<TIM, const C: u8> CPin<TIM, C> for NoPin {
+    const REMAPS: u8 = 0b1111;
+}
+
+macro_rules! channel_pins {
+    ($($TIMX:ident, $C:ident: [$($PIN:ident: $REMAPS:literal),+],)+) => {
+        $($(
+            impl CPin<$TIMX, { super::$C }> for $PIN<Alternate<PushPull>> {
+                const REMAPS: u8 = $REMAPS;
+            }
+
+            impl<MODE> CInPin<$TIMX, { super::$C }> for $PIN<Input<MODE>> {
+                const REMAPS: u8 = $REMAPS;
+            }
+        )+)+
+    };
+}
+
+channel_pins! {
+    TIM1, C1: [PD2: 0b0101, PC6: 0b0010, PC4: 0b1000],
+    TIM1, C2: [PA1: 0b0101, PC7: 0b1010],
+    TIM1, C3: [PC3: 0b0101, PC0: 0b0010, PC5: 0b1000],
+    TIM1, C4: [PC4: 0b0101, PD3: 0b0010, PD4: 0b1000],
+    TIM2, C1: [PD4: 0b0001, PC5: 0b0010, PC1: 0b1100],
+    TIM2, C2: [PD3: 0b0101, PC2: 0b0010, PC7: 0b1000],
+    TIM2, C3: [PC0: 0b0101, PD2: 0b0010, PD6: 0b1000],
+    TIM2, C4: [PD7: 0b0101, PC1: 0b0010, PD5: 0b1000],
+}
+
+macro_rules! complementary_pins {
+    ($($C:ident: [$($PIN:ident: $REMAPS:literal),+],)+) => {
+        $($(
+            impl CNPin<TIM1, { super::$C }> for $PIN<Alternate<PushPull>> {
+                const REMAPS: u8 = $REMAPS;
+            }
+        )+)+
+    };
+}
+
+complementary_pins! {
+    C1: [PD0: 0b0101, PC3: 0b1010],
+    C2: [PA2: 0b0101, PC4: 0b0010, PD2: 0b1000],
+    C3: [PD1: 0b0111, PC6: 0b1000],
+}
+
+/// Break input pin of TIM1
+pub trait BkinPin: sealed::Sealed {
+    /// Bit `n` is set if the pin is connected to the break input with the remap `n` (TIM1_RM)
+    const REMAPS: u8;
+}
+
+impl<MODE> BkinPin for PC2<Input<MODE>> {
+    const REMAPS: u8 = 0b0101;
+}
+
+impl<MODE> BkinPin for PC1<Input<MODE>> {
+    const REMAPS: u8 = 0b1010;
+}
@@ -0,0 +1,347 @@
+//! Pulse width modulation
+//!
+//! [`Timer::pwm`] sets the period of the timer and returns a [`PwmChannel`] for each of its
+//! four channels, in PWM mode 1: the output is high from the start of a period until the
+//! counter reaches the duty cycle.
+//!
+//! The duty resolution depends on the frequency: a period counts `PCLK / freq` timer clock
+//! cycles, divided by the prescaler until it fits in 16 bits. The maximum duty cycle is the
+//! number of steps of the period, e.g. at 24 MHz 24000 steps for 1 kHz or 60000 steps for
+//! a 50 Hz servo signal (prescaled by 8). Frequencies close to PCLK leave only a few steps.
+//!
+//! Channels 1 to 3 of TIM1 also have complementary outputs for half-bridges, see
+//! [`PwmChannel::with_complementary`] and [`Timer::set_dead_time`]. Their break input
+//! ([`Timer::enable_break`]) turns all outputs off on an external fault signal.
+
+use core::{convert::Infallible, marker::PhantomData};
+
+use fugit::{HertzU32 as Hertz, NanosDurationU32};
+
+use super::{psc_arr, BkinPin, CNPin, CPin, Timer, C1, C2, C3, C4};
+use crate::{
+    afio::PCFR,
+    pac::{AFIO, TIM1, TIM2},
+};
+
+/// Channel pins of a timer, as a tuple of four [`CPin`]s in channel order
+///
+/// Use [`NoPin`](crate::gpio::NoPin) for unused channels, the pins select the remap of the
+/// timer. All pins have to be connected under the same remap.
+pub trait PwmPins<TIM> {
+    /// Value of the remap field (TIMx_RM)
+    const REMAP: u8;
+}
+
+impl<TIM, P1, P2, P3, P4> PwmPins<TIM> for (P1, P2, P3, P4)
+where
+    P1: CPin<TIM, C1>,
+    P2: CPin<TIM, C2>,
+    P3: CPin<TIM, C3>,
+    P4: CPin<TIM, C4>,
+{
+    const REMAP: u8 = {
+        let remaps = P1::REMAPS & P2::REMAPS & P3::REMAPS & P4::REMAPS;
+        assert!(remaps != 0, "the timer pins have no common remap");
+        remaps.trailing_zeros() as u8
+    };
+}
+
+/// A PWM output channel `C` of a timer, disabled until [`enable`](Self::enable) is called
+///
+/// Channels without a pin are returned as well, their output goes nowhere. `COMP` is set
+/// for channels of TIM1 that also drive their complementary output.
+pub struct PwmChannel<TIM, const C: u8, const COMP: bool = false> {
+    _tim: PhantomData<TIM>,
+}
+
+impl<TIM, const C: u8, const COMP: bool> embedded_hal_1::pwm::ErrorType
+    for PwmChannel<TIM, C, COMP>
+{
+    type Error = Infallible;
+}
+
+macro_rules! pwm {
+    ($($TIMX:ident: ($set_remap:ident $(, $moe:ident)?),)+) => {
+        $(
+            impl Timer<$TIMX> {
+                /// Output PWM at `freq` on the channel pins `pins`, configured as alternate
+                /// push-pull outputs
+                ///
+                /// The duty cycles start at zero. The frequency is rounded down to a whole
+                /// number of timer clock cycles.
+                pub fn pwm<PINS: PwmPins<$TIMX>>(
+                    &mut self,
+                    _pins: PINS,
+                    freq: Hertz,
+                    pcfr: &mut PCFR,
+                ) -> (
+                    PwmChannel<$TIMX, C1>,
+                    PwmChannel<$TIMX, C2>,
+                    PwmChannel<$TIMX, C3>,
+                    PwmChannel<$TIMX, C4>,
+                ) {
+                    pcfr.$set_remap(PINS::REMAP);
+
+                    self.tim.ctlr1.modify(|_, w| w.cen().clear_bit());
+
+                    // At most 0xffff steps, so that ARR + 1 fits the duty cycle
+                    let (psc, arr) = psc_arr(self.clk.raw() / freq.raw().max(1), 0xffff);
+                    self.tim.psc.write(|w| w.psc().variant(psc));
+                    self.tim.atrlr.write(|w| w.atrlr().variant(arr));
+                    self.tim.ch1cvr.reset();
+                    self.tim.ch2cvr.reset();
+                    self.tim.ch3cvr.reset();
+                    self.tim.ch4cvr.reset();
+
+                    // PWM mode 1 (OCxM = 0b110) on all channels. The compare values are
+                    // preloaded (OCxPE) and the period too (ARPE): new values latch at the
+                    // next update event instead of cutting the current period short.
+                    self.tim
+                        .chctlr1_output()
+                        .write(|w| unsafe { w.bits(0x6868) });
+                    self.tim
+                        .chctlr2_output()
+                        .write(|w| unsafe { w.bits(0x6868) });
+                    $(self.tim.bdtr.modify(|_, w| w.$moe().set_bit());)?
+
+                    self.tim.ctlr1.modify(|_, w| w.arpe().set_bit().urs().set_bit());
+                    self.tim.swevgr.write(|w| w.ug().set_bit());
+                    self.tim.intfr.write(|w| unsafe { w.bits(!1) });
+                    self.tim.ctlr1.modify(|_, w| w.cen().set_bit());
+
+                    (
+                        PwmChannel { _tim: PhantomData },
+                        PwmChannel { _tim: PhantomData },
+                        PwmChannel { _tim: PhantomData },
+                        PwmChannel { _tim: PhantomData },
+                    )
+                }
+            }
+
+            impl<const C: u8, const COMP: bool> PwmChannel<$TIMX, C, COMP> {
+                const CCER_MASK: u32 = (if COMP { 0b101 } else { 0b001 }) << (4 * C);
+
+                #[inline(always)]
+                fn tim(&self) -> &<$TIMX as core::ops::Deref>::Target {
+                    // NOTE(unsafe) the channel only accesses its own bits, or its own register
+                    unsafe { &*$TIMX::ptr() }
+                }
+
+                /// Enable the output of the channel (CCxE), and its complementary output
+                /// (CCxNE)
+                pub fn enable(&mut self) {
+                    let mask = Self::CCER_MASK;
+                    self.tim()
+                        .ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+                }
+
+                /// Disable the outputs of the channel
+                pub fn disable(&mut self) {
+                    let mask = Self::CCER_MASK;
+                    self.tim()
+                        .ccer
+                        .modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+                }
+
+                /// The current duty cycle
+                pub fn get_duty(&self) -> u16 {
+                    let tim = self.tim();
+                    match C {
+                        C1 => tim.ch1cvr.read().bits() as u16,
+                        C2 => tim.ch2cvr.read().bits() as u16,
+                        C3 => tim.ch3cvr.read().bits() as u16,
+                        _ => tim.ch4cvr.read().bits() as u16,
+                    }
+                }
+
+                /// Set the duty cycle, from 0 to [`get_max_duty`](Self::get_max_duty)
+                ///
+                /// Only the compare register (CHxCVR) is written. It is preloaded, the new
+                /// value takes effect at the next update event, so the duty can be changed
+                /// at any time without a glitch in the current period.
+                pub fn set_duty(&mut self, duty: u16) {
+                    let tim = self.tim();
+                    match C {
+                        C1 => tim.ch1cvr.write(|w| w.ch1cvr().variant(duty)),
+                        C2 => tim.ch2cvr.write(|w| w.ch2cvr().variant(duty)),
+                        C3 => tim.ch3cvr.write(|w| w.ch3cvr().variant(duty)),
+                        _ => tim.ch4cvr.write(|w| w.ch4cvr().variant(duty)),
+                    }
+                }
+
+                /// The duty cycle of a constantly high output, ARR + 1 (at most 0xffff)
+                pub fn get_max_duty(&self) -> u16 {
+                    (self.tim().atrlr.read().bits() as u16).saturating_add(1)
+                }
+            }
+
+            impl<const C: u8, const COMP: bool> embedded_hal_1::pwm::SetDutyCycle for PwmChannel<$TIMX, C, COMP> {
+                fn max_duty_cycle(&self) -> u16 {
+                    PwmChannel::<$TIMX, C, COMP>::get_max_duty(self)
+                }
+
+                fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Infallible> {
+                    PwmChannel::<$TIMX, C, COMP>::set_duty(self, duty);
+                    Ok(())
+                }
+            }
+
+            impl<const C: u8, const COMP: bool> embedded_hal_02::PwmPin for PwmChannel<$TIMX, C, COMP> {
+                type Duty = u16;
+
+                fn disable(&mut self) {
+                    PwmChannel::<$TIMX, C, COMP>::disable(self)
+                }
+
+                fn enable(&mut self) {
+                    PwmChannel::<$TIMX, C, COMP>::enable(self)
+                }
+
+                fn get_duty(&self) -> u16 {
+                    PwmChannel::<$TIMX, C, COMP>::get_duty(self)
+                }
+
+                fn get_max_duty(&self) -> u16 {
+                    PwmChannel::<$TIMX, C, COMP>::get_max_duty(self)
+                }
+
+                fn set_duty(&mut self, duty: u16) {
+                    PwmChannel::<$TIMX, C, COMP>::set_duty(self, duty)
+                }
+            }
+        )+
+    };
+}
+
+pwm! {
+    TIM1: (set_tim1_remap, moe),
+    TIM2: (set_tim2_remap),
+}
+
+impl<const C: u8> PwmChannel<TIM1, C> {
+    /// Also drive the complementary output of the channel on `pin`, the inverse of the
+    /// channel output with the dead-time of [`Timer::set_dead_time`] between both edges
+    ///
+    /// Both outputs follow [`enable`](PwmChannel::enable) and
+    /// [`disable`](PwmChannel::disable). Channel 4 has no complementary output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pin` isn't connected to the channel under the remap selected by
+    /// [`Timer::pwm`].
+    pub fn with_complementary<NPIN: CNPin<TIM1, C>>(self, _pin: NPIN) -> PwmChannel<TIM1, C, true> {
+        // NOTE(unsafe) atomic read without side effects
+        let remap = unsafe { (*AFIO::ptr()).pcfr.read().tim1rm().bits() };
+        assert!(
+            NPIN::REMAPS & 1 << remap != 0,
+            "the complementary pin isn't connected under the TIM1 remap"
+        );
+
+        PwmChannel { _tim: PhantomData }
+    }
+}
+
+impl Timer<TIM1> {
+    /// Insert a dead-time between an edge of a channel output and the opposite edge of its
+    /// complementary output (DTG)
+    ///
+    /// The dead-time is rounded up to the resolution of the DTG encoding, one timer clock
+    /// cycle up to 127 cycles and 16 cycles near its maximum of 1008 cycles (42 us at
+    /// 24 MHz), longer times are clamped. The dead-time can't be changed once the BDTR
+    /// register is locked.
+    pub fn set_dead_time(&mut self, dead_time: NanosDurationU32) {
+        let cycles = (dead_time.ticks() as u64 * self.clk.raw() as u64).div_ceil(1_000_000_000);
+        self.tim
+            .bdtr
+            .modify(|_, w| unsafe { w.dtg().bits(dead_time_bits(cycles.min(1008) as u32)) });
+    }
+}
+
+/// Active level of the break input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakPolarity {
+    /// A low input breaks (BKP = 0)
+    ActiveLow,
+    /// A high input breaks (BKP = 1)
+    ActiveHigh,
+}
+
+impl Timer<TIM1> {
+    /// Turn the PWM outputs off while `pin` is at its active level (BKE)
+    ///
+    /// A break clears the main output enable (MOE) in hardware, without software latency.
+    /// The outputs are then driven to their idle level, low, after the dead-time. They stay
+    /// off after the fault clears, until [`resume_outputs`](Self::resume_outputs).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pin` isn't connected to the break input under the remap selected by
+    /// [`Timer::pwm`].
+    pub fn enable_break<PIN: BkinPin>(&mut self, _pin: PIN, polarity: BreakPolarity) {
+        // NOTE(unsafe) atomic read without side effects
+        let remap = unsafe { (*AFIO::ptr()).pcfr.read().tim1rm().bits() };
+        assert!(
+            PIN::REMAPS & 1 << remap != 0,
+            "the break pin isn't connected under the TIM1 remap"
+        );
+
+        // OSSI drives the idle level instead of releasing the pins while MOE is cleared
+        self.tim.bdtr.modify(|_, w| {
+            w.ossi()
+                .set_bit()
+                .bkp()
+                .bit(polarity == BreakPolarity::ActiveHigh)
+                .bke()
+                .set_bit()
+        });
+        self.clear_break();
+    }
+
+    /// Stop monitoring the break input
+    pub fn disable_break(&mut self) {
+        self.tim.bdtr.modify(|_, w| w.bke().clear_bit());
+    }
+
+    /// Turn the outputs back on after a break (MOE)
+    ///
+    /// Returns `false` if the break input is still active: the outputs stay off.
+    pub fn resume_outputs(&mut self) -> bool {
+        self.clear_break();
+        self.tim.bdtr.modify(|_, w| w.moe().set_bit());
+        self.tim.bdtr.read().moe().bit_is_set()
+    }
+
+    /// Raise the TIM1_BRK interrupt on a break (BIE)
+    pub fn listen_break(&mut self) {
+        self.tim.dmaintenr.modify(|_, w| w.bie().set_bit());
+    }
+
+    /// Stop raising the interrupt on a break
+    pub fn unlisten_break(&mut self) {
+        self.tim.dmaintenr.modify(|_, w| w.bie().clear_bit());
+    }
+
+    /// Whether a break occurred since the flag was cleared (BIF)
+    pub fn is_break_pending(&self) -> bool {
+        self.tim.intfr.read().bif().bit_is_set()
+    }
+
+    /// Clear the break flag, which has to be done in the interrupt handler
+    ///
+    /// The flag is set again immediately while the break input is active.
+    pub fn clear_break(&mut self) {
+        // NOTE(unsafe) rc_w0 flags, writing 1 leaves the others unchanged
+        self.tim.intfr.write(|w| unsafe { w.bits(!(1 << 7)) });
+    }
+}
+
+/// Encoding of the dead-time generator (DTG) for at least `cycles` timer clock cycles
+fn dead_time_bits(cycles: u32) -> u8 {
+    match cycles {
+        0..=127 => cycles as u8,
+        128..=254 => 0b1000_0000 | (cycles.div_ceil(2) - 64) as u8,
+        255..=504 => 0b1100_0000 | (cycles.div_ceil(8) - 32) as u8,
+        _ => 0b1110_0000 | (cycles.div_ceil(16) - 32) as u8,
+    }
+}